@@ -0,0 +1,212 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+const LEAF_TAG: u8 = 0;       // leaves and nodes are hashed apart, so a node can't be claimed as a leaf
+const NODE_TAG: u8 = 1;
+
+/*
+a treasury distribution of a closed treasury epoch computed off-chain, every leaf of the merkle tree is
+sha256(0x00 || "epoch_index:account_id:amount") and every node is sha256(0x01 || its two children in ascending order)
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone)]
+pub struct TreasuryDrop {
+    pub epoch_index: u64,       // the treasury epoch distributed
+    pub root: Vec<u8>,
+    pub amount: u128,           // taken from treasury_amount
+    pub claimed_amount: u128,
+    pub expire_time: u64,       // unclaimed amount goes back to treasury after expire_time
+    pub expired: bool,
+}
+
+pub fn drop_leaf(epoch_index: u64, account_id: &AccountId, amount: u128) -> Vec<u8> {
+    env::sha256(&[vec![LEAF_TAG], format!("{}:{}:{}", epoch_index, account_id, amount).into_bytes()].concat())
+}
+
+pub fn drop_node(a: &Vec<u8>, b: &Vec<u8>) -> Vec<u8> {
+    if a <= b {
+        env::sha256(&[vec![NODE_TAG], a.clone(), b.clone()].concat())
+    } else {
+        env::sha256(&[vec![NODE_TAG], b.clone(), a.clone()].concat())
+    }
+}
+
+pub fn verify_proof(root: &Vec<u8>, leaf: Vec<u8>, proof: &Vec<Vec<u8>>) -> bool {
+    let mut hash = leaf;
+    for node in proof.iter() {
+        hash = drop_node(&hash, node);
+    }
+    hash == *root
+}
+
+pub(crate) fn drop_claim_key(drop_id: u64, account_id: &AccountId) -> String {
+    format!("{}:{}", drop_id, account_id)
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+    publish a merkle root of (account, amount) for a closed treasury epoch funded from treasury, returns the drop id.
+    an epoch has one drop at most
+    */
+    pub fn publish_treasury_drop(&mut self, epoch_index: U64, root: Base64VecU8, amount: U128, duration: U64) -> U64 {
+        let sender_id = env::predecessor_account_id();
+        require(sender_id == self.owner_id || sender_id == self.treasurer_id, RoulefiError::OwnerOrTreasurerOnly);
+        let epoch_index = u64::from(epoch_index);
+        let root: Vec<u8> = root.into();
+        let amount = u128::from(amount);
+        require(epoch_index > 0 && epoch_index < self.treasury_status.epoch_index, RoulefiError::InvalidDropEpoch);
+        require(!self.epoch_drops.contains_key(&epoch_index), RoulefiError::InvalidDropEpoch);
+        require(root.len() == 32, RoulefiError::InvalidMerkleRoot);
+        require(amount > 0, RoulefiError::NotEnoughAmount);
+        require(u64::from(duration) > 0, RoulefiError::InvalidDropDuration);
+        require(amount <= self.treasury_status.treasury_amount, RoulefiError::NotEnoughTreasury);
+        self.treasury_status.treasury_amount = safe_sub(self.treasury_status.treasury_amount, amount);
+        self.treasury_status.unclaimed_amount = safe_add(self.treasury_status.unclaimed_amount, amount);
+        self.epoch_drops.insert(&epoch_index, &self.treasury_drops.len());
+        self.treasury_drops.push(&TreasuryDrop {
+            epoch_index,
+            root,
+            amount,
+            claimed_amount: 0,
+            expire_time: safe_time_after(env::block_timestamp(), duration.into()),   // duration in seconds
+            expired: false,
+        });
+        self.assert_solvent();
+        U64::from(self.treasury_drops.len() - 1)
+    }
+
+    /*
+    claim the amount of a treasury drop to balance with the merkle proof
+    */
+    pub fn claim_with_proof(&mut self, drop_id: U64, amount: U128, proof: Vec<Base64VecU8>) {
+        let sender_id = env::predecessor_account_id();
+        let drop_id = u64::from(drop_id);
+        let amount = u128::from(amount);
+        let mut drop = self.treasury_drops.get(drop_id).or_fail(RoulefiError::DropNotFound);
+        require(!drop.expired && env::block_timestamp() <= drop.expire_time, RoulefiError::DropExpired);
+        require(self.drop_claims.insert(&drop_claim_key(drop_id, &sender_id)), RoulefiError::AlreadyClaimed);
+        let proof: Vec<Vec<u8>> = proof.into_iter().map(|node| node.into()).collect();
+        require(verify_proof(&drop.root, drop_leaf(drop.epoch_index, &sender_id, amount), &proof), RoulefiError::InvalidProof);
+        drop.claimed_amount = safe_add(drop.claimed_amount, amount);
+        require(drop.claimed_amount <= drop.amount, RoulefiError::ExceedDropAmount);
+        self.treasury_drops.replace(drop_id, &drop);
+
+        let mut account = self.accounts.get(&sender_id).unwrap_or(new_user());
+        account.balance = safe_add(account.balance, amount);
+        self.accounts.insert(&sender_id, &account);
+        self.treasury_status.unclaimed_amount = safe_sub(self.treasury_status.unclaimed_amount, amount);
+        self.assert_solvent();
+    }
+
+    /*
+    give the unclaimed amount of an expired drop back to treasury, anyone can call
+    */
+    pub fn expire_treasury_drop(&mut self, drop_id: U64) {
+        let drop_id = u64::from(drop_id);
+        let mut drop = self.treasury_drops.get(drop_id).or_fail(RoulefiError::DropNotFound);
+        require(!drop.expired, RoulefiError::DropExpired);
+        require(env::block_timestamp() > drop.expire_time, RoulefiError::DropNotExpired);
+        let amount = safe_sub(drop.amount, drop.claimed_amount);
+        drop.expired = true;
+        self.treasury_drops.replace(drop_id, &drop);
+        self.treasury_status.unclaimed_amount = safe_sub(self.treasury_status.unclaimed_amount, amount);
+        self.treasury_status.treasury_amount = safe_add(self.treasury_status.treasury_amount, amount);
+        self.assert_solvent();
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    #[test]
+    fn claim_and_expire_drop() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.treasury_status.treasury_amount = 1000;
+        contract.treasury_status.epoch_index = 2;
+
+        let bob_leaf = drop_leaf(1, &"bob".to_string(), 300);
+        let carol_leaf = drop_leaf(1, &"carol".to_string(), 200);
+        let root = drop_node(&bob_leaf, &carol_leaf);
+        let drop_id = contract.publish_treasury_drop(U64::from(1), Base64VecU8::from(root), U128::from(500), U64::from(60));
+        assert_eq!(contract.treasury_status.treasury_amount, 500);
+
+        contract.claim_with_proof(drop_id.clone(), U128::from(300), vec![Base64VecU8::from(carol_leaf)]);
+        assert_eq!(contract.accounts.get(&"bob".to_string()).unwrap().balance, 300);
+        assert_eq!(contract.treasury_status.unclaimed_amount, 200);
+
+        let mut context = get_context();
+        context.block_timestamp += 61 * 1000000000;
+        testing_env!(context);
+        contract.expire_treasury_drop(drop_id);
+        assert_eq!(contract.treasury_status.treasury_amount, 700);
+        assert_eq!(contract.treasury_status.unclaimed_amount, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid proof")]
+    fn claim_with_wrong_amount() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.treasury_status.treasury_amount = 1000;
+        contract.treasury_status.epoch_index = 2;
+
+        let bob_leaf = drop_leaf(1, &"bob".to_string(), 300);
+        let carol_leaf = drop_leaf(1, &"carol".to_string(), 200);
+        let root = drop_node(&bob_leaf, &carol_leaf);
+        let drop_id = contract.publish_treasury_drop(U64::from(1), Base64VecU8::from(root), U128::from(500), U64::from(60));
+        contract.claim_with_proof(drop_id, U128::from(400), vec![Base64VecU8::from(carol_leaf)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "closed epoch without a drop")]
+    fn one_drop_per_epoch() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.treasury_status.treasury_amount = 1000;
+        contract.treasury_status.epoch_index = 2;
+        contract.publish_treasury_drop(U64::from(1), Base64VecU8::from(vec![0u8; 32]), U128::from(500), U64::from(60));
+        contract.publish_treasury_drop(U64::from(1), Base64VecU8::from(vec![1u8; 32]), U128::from(500), U64::from(60));
+    }
+
+    #[test]
+    #[should_panic(expected = "E090: arithmetic overflow")]
+    fn drop_duration_overflow() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.treasury_status.treasury_amount = 1000;
+        contract.treasury_status.epoch_index = 2;
+        contract.publish_treasury_drop(U64::from(1), Base64VecU8::from(vec![0u8; 32]), U128::from(500), U64::from(u64::MAX / 1000));
+    }
+}
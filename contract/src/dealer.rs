@@ -1,9 +1,33 @@
 
 use crate::*;
+use crate::limits::LimitKind;
 
 
-
-
+impl Contract {
+    /*
+    add a new stake to the pool
+    */
+    pub(crate) fn internal_stake(&mut self, account_id: &AccountId, account: &mut Account, amount: u128) {
+        require(amount > 0, RoulefiError::NotEnoughAmount);
+        let now = env::block_timestamp();
+        self.round_status.stake_amount = safe_add(self.round_status.stake_amount, amount);
+        if account.stakes.len() == 0 {
+            self.stake_accounts.insert(account_id);
+        }
+        let mut stake = Stake {
+            amount,
+            time: now,
+            profit: 0,
+            loss: 0,
+            reward_index: self.treasury_status.stake_reward_index,
+            reward_weight: 0,
+            reward: 0,
+        };
+        self.internal_update_reward_weight(&mut stake);
+        account.stakes.push(stake);
+        self.cal_max_amount_allowed();
+    }
+}
 
 
 #[near_bindgen]
@@ -15,20 +39,42 @@ impl Contract {
     #[payable]
     pub fn stake(&mut self) {                        
         let sender_id = env::predecessor_account_id();
+        self.assert_not_excluded(&sender_id);
+        self.internal_use_limit(&sender_id, LimitKind::Deposit, env::attached_deposit());
         let mut account = self.accounts.get(&sender_id).unwrap_or(new_user());
-        let now = env::block_timestamp();
-        self.round_status.stake_amount = self.round_status.stake_amount + env::attached_deposit();
-        if account.stakes.len() == 0 {
-            self.stake_accounts.insert(&sender_id.clone());
+        self.internal_stake(&sender_id, &mut account, env::attached_deposit());
+        self.accounts.insert(&sender_id, &account);
+        self.assert_solvent();
+    }
+
+    /*
+    stake from balance in the contract
+    */
+    pub fn stake_from_balance(&mut self, amount: U128) {
+        let sender_id = env::predecessor_account_id();
+        self.assert_not_excluded(&sender_id);
+        let mut account = self.accounts.get(&sender_id).or_fail(RoulefiError::AccountNotFound);
+        let amount = u128::from(amount);
+        require(amount <= account.balance, RoulefiError::NotEnoughBalance);
+        self.internal_use_limit(&sender_id, LimitKind::Wager, amount);
+        account.balance = safe_sub(account.balance, amount);
+        if account.bets.len() > 0 {
+            self.assert_gas_reserved(&account);
         }
-        account.stakes.push(Stake {
-            amount: env::attached_deposit(),
-            time: now,
-            profit: 0,
-            loss: 0
-        });
+        self.internal_stake(&sender_id, &mut account, amount);
         self.accounts.insert(&sender_id, &account);
-        self.cal_max_amount_allowed();
+        self.assert_solvent();
+    }
+
+    /*
+    treasury rewards go to a new stake instead of balance when auto compound is on
+    */
+    pub fn set_auto_compound(&mut self, enabled: bool) {
+        let sender_id = env::predecessor_account_id();
+        let mut account = self.accounts.get(&sender_id).unwrap_or(new_user());
+        account.auto_compound = enabled;
+        self.accounts.insert(&sender_id, &account);
+        self.assert_solvent();
     }
 
     /*
@@ -36,17 +82,19 @@ impl Contract {
     */
     pub fn unstake(&mut self, index: usize) {
         let sender_id = env::predecessor_account_id();
-        let mut account = self.accounts.get(&sender_id).unwrap();
-        let stake = account.stakes.get_mut(index).unwrap();
-        let amount = stake.amount + stake.profit - stake.loss;
-        assert!(u64::from(stake.time) < u64::from(env::block_timestamp()), "in lock period");
-        assert!(amount > 0, "not enough amount!");
-        assert!(amount <= env::account_balance(), "not enough balance!");
+        let mut account = self.accounts.get(&sender_id).or_fail(RoulefiError::AccountNotFound);
+        self.internal_claim_treasury(&sender_id, &mut account, true);   // claim the treasury reward of the stakes first
+        let stake = account.stakes.get_mut(index).or_fail(RoulefiError::StakeNotFound);
+        let amount = safe_sub(safe_add(stake.amount, stake.profit), stake.loss);
+        require(u64::from(stake.time) < u64::from(env::block_timestamp()), RoulefiError::InLockPeriod);
+        require(amount > 0, RoulefiError::NotEnoughAmount);
+        require(amount <= env::account_balance(), RoulefiError::NotEnoughContractBalance);
 
         
-        self.round_status.stake_amount -= stake.amount;
-        self.round_status.profit_amount -= stake.profit;
-        self.round_status.loss_amount -= stake.loss;
+        self.round_status.stake_amount = safe_sub(self.round_status.stake_amount, stake.amount);
+        self.round_status.profit_amount = safe_sub(self.round_status.profit_amount, stake.profit);
+        self.round_status.loss_amount = safe_sub(self.round_status.loss_amount, stake.loss);
+        self.treasury_status.stake_weight = safe_sub(self.treasury_status.stake_weight, stake.reward_weight);
         
         account.stakes.remove(index);
         
@@ -56,6 +104,7 @@ impl Contract {
         self.accounts.insert(&sender_id, &account);
         self.cal_max_amount_allowed();
         Promise::new(sender_id).transfer(amount.clone());
+        self.assert_solvent();
     }
 
 }
@@ -67,6 +116,7 @@ mod tests {
     use near_sdk::MockedBlockchain;
     use near_sdk::testing_env;
     use near_sdk::VMContext;
+    use crate::internal::seniority_rate;
     use near_sdk::json_types::ValidAccountId;
     use near_sdk::serde::export::TryFrom;
 
@@ -85,8 +135,8 @@ mod tests {
             predecessor_account_id: "bob".to_string(),
             input: vec![],
             block_index: 0,
-            block_timestamp: env::block_timestamp() - 100,
-            account_balance: 100000000000000000,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
             account_locked_balance: 0,
             storage_usage: 10u64.pow(6),
             attached_deposit: 10000000000000000,
@@ -109,6 +159,93 @@ mod tests {
         contract.cal_profit(123, 123);
     }
 
+    #[test]
+    fn insurance_takes_loss_first() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.stake();
+
+        contract.cal_profit(1000, 0);
+        assert_eq!(contract.treasury_status.treasury_amount, 100);
+        assert_eq!(contract.treasury_status.insurance_amount, 90);
+        assert_eq!(contract.round_status.profit_amount, 810);
+
+        contract.cal_profit(0, 500);
+        assert_eq!(contract.treasury_status.insurance_amount, 0);
+        assert_eq!(contract.round_status.profit_amount, 400);
+        assert_eq!(contract.round_status.loss_amount, 0);
+    }
+
+    #[test]
+    fn replenish_pool_under_water() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.stake();
+
+        contract.cal_profit(0, 500);
+        assert_eq!(contract.round_status.loss_amount, 500);
+        contract.cal_profit(100, 0);
+        assert_eq!(contract.treasury_status.insurance_amount, 0);   // no insurance when the pool is under water
+        assert_eq!(contract.round_status.loss_amount, 410);
+
+        contract.treasury_status.insurance_amount = 300;
+        assert_eq!(contract.replenish_pool(), U128::from(300));
+        assert_eq!(contract.treasury_status.insurance_amount, 0);
+        assert_eq!(contract.round_status.loss_amount, 110);
+    }
+
+    #[test]
+    fn seniority_rate_steps() {
+        let step_time = vec![0, 604800, 2592000];
+        let step_rate = vec![0, 5, 20];
+        let curve = SeniorityCurve::Step;
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 0), 100);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 604799), 100);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 604800), 105);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 2591999), 105);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 2592000), 120);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 100000000), 120);
+    }
+
+    #[test]
+    fn seniority_rate_linear() {
+        let step_time = vec![100, 200, 400];
+        let step_rate = vec![10, 20, 40];
+        let curve = SeniorityCurve::Linear;
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 0), 100);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 99), 100);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 100), 110);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 150), 115);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 200), 120);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 300), 130);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 400), 140);
+        assert_eq!(seniority_rate(&step_time, &step_rate, &curve, 5000), 140);
+    }
+
+    #[test]
+    fn seniority_bonus_in_profit() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.stake();
+
+        let mut context = get_context();
+        context.predecessor_account_id = "carol".to_string();
+        context.block_timestamp += 2592000 * 1000000000;   // one month later
+        testing_env!(context);
+        contract.stake();
+        assert_eq!(contract.cal_time_rate(&contract.accounts.get(&"bob".to_string()).unwrap().stakes[0]), 120);
+        assert_eq!(contract.cal_time_rate(&contract.accounts.get(&"carol".to_string()).unwrap().stakes[0]), 100);
+
+        contract.config.treasury_rate = 0;
+        contract.config.insurance_rate = 0;
+        contract.cal_profit(2200, 0);
+        assert_eq!(contract.accounts.get(&"bob".to_string()).unwrap().stakes[0].profit, 1200);
+        assert_eq!(contract.accounts.get(&"carol".to_string()).unwrap().stakes[0].profit, 1000);
+    }
+
     #[test]
     fn stake() {
         let context = get_context();
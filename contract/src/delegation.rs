@@ -0,0 +1,301 @@
+use crate::*;
+use near_sdk::{ext_contract, PromiseResult};
+use near_sdk::json_types::{ValidAccountId, I128};
+
+const GAS_FOR_VALIDATOR: Gas = 50_000_000_000_000;
+const GAS_FOR_CALLBACK: Gas = 20_000_000_000_000;
+const NUM_EPOCHS_TO_UNLOCK: u64 = 4;     // unstaked balance of a validator pool is locked for 4 epochs
+
+/*
+pool liquidity delegated to a validator pool
+*/
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug, Clone)]
+pub struct DelegationStatus {
+    pub validator_id: Option<AccountId>,
+    pub staked_amount: u128,        // staked in the validator pool
+    pub unstaked_amount: u128,      // unstaked and waiting for withdraw
+    pub unstake_epoch: u64,         // epoch of the last unstake
+    pub pending_amount: u128,       // sent to the validator pool and not confirmed yet
+    pub pending: bool,              // a call to the validator pool is in progress
+}
+
+#[ext_contract(ext_staking_pool)]
+pub trait StakingPool {
+    fn deposit_and_stake(&mut self);
+    fn unstake(&mut self, amount: U128);
+    fn withdraw(&mut self, amount: U128);
+    fn get_account_total_balance(&self, account_id: AccountId) -> U128;
+}
+
+#[ext_contract(ext_self)]
+pub trait DelegationCallbacks {
+    fn on_delegated(&mut self, amount: U128) -> bool;
+    fn on_undelegated(&mut self, amount: U128) -> bool;
+    fn on_delegation_withdrawn(&mut self, amount: U128) -> bool;
+    fn on_delegation_synced(&mut self) -> I128;
+}
+
+fn is_promise_success() -> bool {
+    require(env::promise_results_count() == 1, RoulefiError::InvalidPromiseResult);
+    match env::promise_result(0) {
+        PromiseResult::Successful(_) => true,
+        _ => false,
+    }
+}
+
+impl Contract {
+    /*
+    liquidity above max_amount_allowed can be delegated, delegate_rate of it at most
+    */
+    pub(crate) fn cal_delegable_amount(&self) -> u128 {
+        let idle_amount = self.pool_amount().saturating_sub(self.round_status.max_amount_allowed);
+        let target = safe_mul_div(idle_amount, self.config.delegate_rate as u128, 100);
+        target.saturating_sub(safe_add(self.delegation.staked_amount, self.delegation.unstaked_amount))
+    }
+
+    fn assert_delegation_idle(&self) {
+        self.assert_owner();
+        require(!self.delegation.pending, RoulefiError::DelegationPending);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+    stake the delegable liquidity of the pool to a whitelisted validator pool
+    */
+    pub fn delegate(&mut self, validator_id: ValidAccountId) -> Promise {
+        self.assert_delegation_idle();
+        let validator_id: AccountId = validator_id.into();
+        require(self.config.validators.contains(&validator_id), RoulefiError::ValidatorNotWhitelisted);
+        if let Some(current_id) = &self.delegation.validator_id {
+            require(*current_id == validator_id || safe_add(self.delegation.staked_amount, self.delegation.unstaked_amount) == 0, RoulefiError::ValidatorInUse);
+        }
+        let amount = self.cal_delegable_amount();
+        require(amount > 0, RoulefiError::NothingToDelegate);
+        require(amount <= env::account_balance(), RoulefiError::NotEnoughContractBalance);
+        self.delegation.validator_id = Some(validator_id.clone());
+        self.delegation.pending = true;
+        self.delegation.pending_amount = amount;
+        let promise = ext_staking_pool::deposit_and_stake(&validator_id, amount, GAS_FOR_VALIDATOR)
+            .then(ext_self::on_delegated(U128::from(amount), &env::current_account_id(), 0, GAS_FOR_CALLBACK));
+        self.assert_solvent();
+        promise
+    }
+
+    /*
+    unstake from the validator pool, it can be withdrawn after 4 epochs
+    */
+    pub fn undelegate(&mut self, amount: U128) -> Promise {
+        self.assert_delegation_idle();
+        let amount = u128::from(amount);
+        require(amount > 0 && amount <= self.delegation.staked_amount, RoulefiError::NotEnoughDelegated);
+        let validator_id = self.delegation.validator_id.clone().or_fail(RoulefiError::NoValidator);
+        self.delegation.pending = true;
+        self.assert_solvent();
+        ext_staking_pool::unstake(U128::from(amount), &validator_id, 0, GAS_FOR_VALIDATOR)
+            .then(ext_self::on_undelegated(U128::from(amount), &env::current_account_id(), 0, GAS_FOR_CALLBACK))
+    }
+
+    /*
+    withdraw the unstaked amount back to the contract
+    */
+    pub fn withdraw_delegation(&mut self) -> Promise {
+        self.assert_delegation_idle();
+        let amount = self.delegation.unstaked_amount;
+        require(amount > 0, RoulefiError::NothingToWithdraw);
+        require(env::epoch_height() >= self.delegation.unstake_epoch + NUM_EPOCHS_TO_UNLOCK, RoulefiError::DelegationLocked);
+        let validator_id = self.delegation.validator_id.clone().or_fail(RoulefiError::NoValidator);
+        self.delegation.pending = true;
+        self.assert_solvent();
+        ext_staking_pool::withdraw(U128::from(amount), &validator_id, 0, GAS_FOR_VALIDATOR)
+            .then(ext_self::on_delegation_withdrawn(U128::from(amount), &env::current_account_id(), 0, GAS_FOR_CALLBACK))
+    }
+
+    /*
+    anyone can sync the staking rewards of the validator pool to stakes
+    */
+    pub fn sync_delegation(&mut self) -> Promise {
+        require(!self.delegation.pending, RoulefiError::DelegationPending);
+        let validator_id = self.delegation.validator_id.clone().or_fail(RoulefiError::NoValidator);
+        self.delegation.pending = true;
+        self.assert_solvent();
+        ext_staking_pool::get_account_total_balance(env::current_account_id(), &validator_id, 0, GAS_FOR_VALIDATOR)
+            .then(ext_self::on_delegation_synced(&env::current_account_id(), 0, GAS_FOR_CALLBACK))
+    }
+
+    #[private]
+    pub fn on_delegated(&mut self, amount: U128) -> bool {
+        self.delegation.pending = false;
+        self.delegation.pending_amount = 0;
+        let success = is_promise_success();
+        if success {
+            self.delegation.staked_amount = safe_add(self.delegation.staked_amount, u128::from(amount));
+            self.assert_solvent();   // a failed deposit is refunded by a receipt of its own
+        }
+        success
+    }
+
+    #[private]
+    pub fn on_undelegated(&mut self, amount: U128) -> bool {
+        self.delegation.pending = false;
+        let success = is_promise_success();
+        if success {
+            self.delegation.staked_amount = safe_sub(self.delegation.staked_amount, u128::from(amount));
+            self.delegation.unstaked_amount = safe_add(self.delegation.unstaked_amount, u128::from(amount));
+            self.delegation.unstake_epoch = env::epoch_height();
+            self.assert_solvent();
+        }
+        success
+    }
+
+    #[private]
+    pub fn on_delegation_withdrawn(&mut self, amount: U128) -> bool {
+        self.delegation.pending = false;
+        let success = is_promise_success();
+        if success {
+            self.delegation.unstaked_amount = safe_sub(self.delegation.unstaked_amount, u128::from(amount));   // the amount comes back by a transfer receipt of its own, solvency is checked by the next call
+        }
+        success
+    }
+
+    /*
+    the balance above what was delegated is the staking reward, it goes to stakes as pool profit.
+    a balance below it is lost by the validator pool, stakes take it as pool loss. returns the change
+    */
+    #[private]
+    pub fn on_delegation_synced(&mut self) -> I128 {
+        self.delegation.pending = false;
+        require(env::promise_results_count() == 1, RoulefiError::InvalidPromiseResult);
+        let total_balance: u128 = match env::promise_result(0) {
+            PromiseResult::Successful(value) => near_sdk::serde_json::from_slice::<U128>(&value).ok().or_fail(RoulefiError::InvalidPromiseResult).into(),
+            _ => return I128::from(0),
+        };
+        let delegated_amount = safe_add(self.delegation.staked_amount, self.delegation.unstaked_amount);
+        if total_balance > delegated_amount {
+            let reward = total_balance - delegated_amount;
+            self.delegation.staked_amount = safe_add(self.delegation.staked_amount, reward);
+            self.distribute_pool(reward, true);
+            self.record_pool_history(reward, 0);
+        } else if total_balance < delegated_amount {
+            let loss = delegated_amount - total_balance;
+            let staked_loss = std::cmp::min(loss, self.delegation.staked_amount);   // the staked part takes the loss first
+            self.delegation.staked_amount -= staked_loss;
+            self.delegation.unstaked_amount = safe_sub(self.delegation.unstaked_amount, loss - staked_loss);
+            self.distribute_pool(loss, false);
+            self.record_pool_history(0, loss);
+        } else {
+            return I128::from(0);
+        }
+        self.cal_max_amount_allowed();
+        self.assert_solvent();
+        I128::from(total_balance as i128 - delegated_amount as i128)
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    // the callbacks are private, the contract calls them on itself with the result of the validator pool
+    fn callback_context(result: PromiseResult) {
+        let mut context = get_context();
+        context.predecessor_account_id = "alice".to_string();
+        testing_env!(context, Default::default(), Default::default(), Default::default(), vec![result]);
+    }
+
+    fn delegated_pool() -> Contract {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100000;
+        testing_env!(context);
+        contract.stake();
+        contract.delegation.validator_id = Some("validator".to_string());
+        contract.delegation.staked_amount = CHIP_UNIT * 40000;
+        contract.delegation.unstaked_amount = CHIP_UNIT * 5000;
+        contract.delegation.pending = true;
+        contract
+    }
+
+    fn balance_result(amount: u128) -> PromiseResult {
+        PromiseResult::Successful(near_sdk::serde_json::to_vec(&U128::from(amount)).unwrap())
+    }
+
+    #[test]
+    fn sync_reward_as_pool_profit() {
+        let mut contract = delegated_pool();
+        let pool_amount = contract.pool_amount();
+        callback_context(balance_result(CHIP_UNIT * 45100));
+        assert_eq!(contract.on_delegation_synced(), I128::from((CHIP_UNIT * 100) as i128));
+        assert!(!contract.delegation.pending);
+        assert_eq!(contract.delegation.staked_amount, CHIP_UNIT * 40100);
+        assert_eq!(contract.pool_amount(), pool_amount + CHIP_UNIT * 100);
+    }
+
+    #[test]
+    fn sync_loss_as_pool_loss() {
+        let mut contract = delegated_pool();
+        let pool_amount = contract.pool_amount();
+        callback_context(balance_result(CHIP_UNIT * 4000));
+        assert_eq!(contract.on_delegation_synced(), I128::from(-((CHIP_UNIT * 41000) as i128)));
+        assert_eq!(contract.delegation.staked_amount, 0);
+        assert_eq!(contract.delegation.unstaked_amount, CHIP_UNIT * 4000);
+        assert_eq!(contract.pool_amount(), pool_amount - CHIP_UNIT * 41000);
+        let bob = contract.accounts.get(&"bob".to_string()).unwrap();
+        assert_eq!(bob.stakes[0].loss, CHIP_UNIT * 41000);
+    }
+
+    #[test]
+    fn failed_sync_changes_nothing() {
+        let mut contract = delegated_pool();
+        callback_context(PromiseResult::Failed);
+        assert_eq!(contract.on_delegation_synced(), I128::from(0));
+        assert!(!contract.delegation.pending);
+        assert_eq!(contract.delegation.staked_amount, CHIP_UNIT * 40000);
+    }
+
+    #[test]
+    fn failed_delegation_not_counted() {
+        let mut contract = delegated_pool();
+        contract.delegation.pending_amount = CHIP_UNIT * 1000;
+        callback_context(PromiseResult::Failed);
+        assert!(!contract.on_delegated(U128::from(CHIP_UNIT * 1000)));
+        assert_eq!(contract.delegation.staked_amount, CHIP_UNIT * 40000);
+        assert_eq!(contract.delegation.pending_amount, 0);
+
+        contract.delegation.pending = true;
+        callback_context(PromiseResult::Successful(vec![]));
+        assert!(contract.on_undelegated(U128::from(CHIP_UNIT * 10000)));
+        assert_eq!(contract.delegation.staked_amount, CHIP_UNIT * 30000);
+        assert_eq!(contract.delegation.unstaked_amount, CHIP_UNIT * 15000);
+    }
+}
@@ -0,0 +1,368 @@
+use crate::*;
+use std::fmt;
+
+/*
+every failure of the contract. the panic message is "E<code>: <message>" so the frontend can map the code
+to a user message, codes are stable and a new error takes a new code
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoulefiError {
+    // access
+    OwnerOnly = 1,
+    OwnerOrTreasurerOnly = 2,
+    AlreadyInitialized = 3,
+    // rounds and bets
+    WheelSpinning = 10,
+    WrongRoundIndex = 11,
+    BettingClosed = 12,
+    EmptyBets = 13,
+    AlreadyBet = 14,
+    NoBetsInRound = 15,
+    TooQuickToSpin = 16,
+    RoundWithoutBets = 17,
+    TooQuickToRefund = 18,
+    ExceedMaxAmountAllowed = 19,
+    InvalidBetType = 20,
+    InvalidBetNumber = 21,
+    InvalidChips = 22,
+    LessThanMinBet = 23,
+    ExceedMaxBet = 24,
+    // balances and stakes
+    AccountNotFound = 30,
+    NotEnoughBalance = 31,
+    NotEnoughContractBalance = 32,
+    NotEnoughAmount = 33,
+    GasNotReserved = 34,
+    StakeNotFound = 35,
+    InLockPeriod = 36,
+    // treasury and insurance
+    TooQuickForTreasury = 40,
+    NotEnoughTreasury = 41,
+    NothingToRelease = 42,
+    NothingToReplenish = 43,
+    // treasury drops
+    InvalidMerkleRoot = 50,
+    DropNotFound = 51,
+    DropExpired = 52,
+    AlreadyClaimed = 53,
+    InvalidProof = 54,
+    ExceedDropAmount = 55,
+    DropNotExpired = 56,
+    InvalidDropDuration = 57,
+    InvalidDropEpoch = 58,
+    // delegation
+    DelegationPending = 60,
+    ValidatorNotWhitelisted = 61,
+    ValidatorInUse = 62,
+    NothingToDelegate = 63,
+    NotEnoughDelegated = 64,
+    NothingToWithdraw = 65,
+    DelegationLocked = 66,
+    NoValidator = 67,
+    InvalidPromiseResult = 68,
+    // owner settings and views
+    DuplicatedBeneficiary = 70,
+    InvalidShares = 71,
+    InvalidVesting = 72,
+    InvalidInsuranceRate = 73,
+    InvalidSeniority = 74,
+    SeniorityNotAscending = 75,
+    InvalidDelegateRate = 76,
+    InvalidLimitsLength = 77,
+    InvalidChipUnit = 78,
+    InvalidLimits = 79,
+    InvalidDays = 80,
+    InvalidBetCloseDelta = 81,
+    InvalidRefundDelay = 82,
+    // math and solvency
+    Overflow = 90,
+    Underflow = 91,
+    Insolvent = 92,
+    // responsible gaming
+    SelfExcluded = 100,
+    ExceedDepositLimit = 101,
+    ExceedLossLimit = 102,
+    ExceedWagerLimit = 103,
+    InvalidExclusion = 104,
+    // referrals
+    AlreadyReferred = 110,
+    InvalidReferrer = 111,
+    InvalidReferralPolicy = 112,
+    // jackpot
+    JackpotClosed = 120,
+    InvalidJackpotRules = 121,
+    // standing orders
+    StandingOrdersFull = 130,
+    StandingOrderNotFound = 131,
+
+    // strategies
+    InvalidStrategy = 140,
+    StrategyNotFound = 141,
+
+    // tournaments
+    TournamentNotFound = 150,
+    TournamentClosed = 151,
+    TournamentFull = 152,
+    AlreadyInTournament = 153,
+    NotInTournament = 154,
+    NotEnoughTournamentChips = 155,
+    TournamentNotOver = 156,
+    TournamentRoundInPlay = 157,
+    TournamentBetsPlaced = 158,
+    InvalidTournament = 159,
+
+    // stats
+    InvalidStatsWindow = 160,
+
+    // private tables
+    TableNotFound = 170,
+    TableClosed = 171,
+    NotAllowlisted = 172,
+    HostOnly = 173,
+    InvalidTable = 174,
+    TableRoundInPlay = 175,
+    TableFeeNotPaid = 176,
+}
+
+impl RoulefiError {
+    pub fn code(&self) -> u32 {
+        *self as u32
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            RoulefiError::OwnerOnly => "owner only",
+            RoulefiError::OwnerOrTreasurerOnly => "owner or treasurer only",
+            RoulefiError::AlreadyInitialized => "Already initialized",
+            RoulefiError::WheelSpinning => "wheel spinning, try later",
+            RoulefiError::WrongRoundIndex => "uncorrect round index",
+            RoulefiError::BettingClosed => "betting closed for this round",
+            RoulefiError::EmptyBets => "you have 0 bets",
+            RoulefiError::AlreadyBet => "you've already bet",
+            RoulefiError::NoBetsInRound => "you have no bets in this round",
+            RoulefiError::TooQuickToSpin => "too quick to spin",
+            RoulefiError::RoundWithoutBets => "no bets",
+            RoulefiError::TooQuickToRefund => "too quick to refund",
+            RoulefiError::ExceedMaxAmountAllowed => "exceed max bet amount allowed",
+            RoulefiError::InvalidBetType => "invalid bet type",
+            RoulefiError::InvalidBetNumber => "invalid bet number",
+            RoulefiError::InvalidChips => "chips must be a multiple of chip unit",
+            RoulefiError::LessThanMinBet => "less than min bet",
+            RoulefiError::ExceedMaxBet => "exceed max bet",
+            RoulefiError::AccountNotFound => "account not found",
+            RoulefiError::NotEnoughBalance => "not enough balance",
+            RoulefiError::NotEnoughContractBalance => "not enough contract balance",
+            RoulefiError::NotEnoughAmount => "not enough amount!",
+            RoulefiError::GasNotReserved => "not enough balance for gas fee",
+            RoulefiError::StakeNotFound => "stake not found",
+            RoulefiError::InLockPeriod => "in lock period",
+            RoulefiError::TooQuickForTreasury => "too quick for treasury",
+            RoulefiError::NotEnoughTreasury => "not enough treasury",
+            RoulefiError::NothingToRelease => "nothing to release",
+            RoulefiError::NothingToReplenish => "nothing to replenish",
+            RoulefiError::InvalidMerkleRoot => "invalid merkle root",
+            RoulefiError::DropNotFound => "drop not found",
+            RoulefiError::DropExpired => "drop expired",
+            RoulefiError::AlreadyClaimed => "already claimed",
+            RoulefiError::InvalidProof => "invalid proof",
+            RoulefiError::ExceedDropAmount => "exceed drop amount",
+            RoulefiError::DropNotExpired => "drop not expired yet",
+            RoulefiError::InvalidDropDuration => "drop duration must be greater than 0",
+            RoulefiError::InvalidDropEpoch => "drop must be for a closed epoch without a drop",
+            RoulefiError::DelegationPending => "delegation in progress",
+            RoulefiError::ValidatorNotWhitelisted => "validator not whitelisted",
+            RoulefiError::ValidatorInUse => "withdraw from current validator first",
+            RoulefiError::NothingToDelegate => "nothing to delegate",
+            RoulefiError::NotEnoughDelegated => "not enough delegated amount",
+            RoulefiError::NothingToWithdraw => "nothing to withdraw",
+            RoulefiError::DelegationLocked => "unstaked amount is locked",
+            RoulefiError::NoValidator => "no validator",
+            RoulefiError::InvalidPromiseResult => "invalid promise result",
+            RoulefiError::DuplicatedBeneficiary => "duplicated beneficiary",
+            RoulefiError::InvalidShares => "shares must add up to 10000",
+            RoulefiError::InvalidVesting => "cliff exceeds duration",
+            RoulefiError::InvalidInsuranceRate => "invalid insurance rate",
+            RoulefiError::InvalidSeniority => "invalid seniority schedule",
+            RoulefiError::SeniorityNotAscending => "step time must be ascending",
+            RoulefiError::InvalidDelegateRate => "invalid delegate rate",
+            RoulefiError::InvalidLimitsLength => "limits must be set for every bet type",
+            RoulefiError::InvalidChipUnit => "chip unit must be greater than 0",
+            RoulefiError::InvalidLimits => "invalid limits",
+            RoulefiError::InvalidDays => "invalid days",
+            RoulefiError::InvalidBetCloseDelta => "bet close delta must be less than round delta",
+            RoulefiError::InvalidRefundDelay => "refund delay too short",
+            RoulefiError::Overflow => "arithmetic overflow",
+            RoulefiError::Underflow => "arithmetic underflow",
+            RoulefiError::Insolvent => "insolvent",
+            RoulefiError::SelfExcluded => "self excluded",
+            RoulefiError::ExceedDepositLimit => "exceed deposit limit",
+            RoulefiError::ExceedLossLimit => "exceed loss limit",
+            RoulefiError::ExceedWagerLimit => "exceed wager limit",
+            RoulefiError::InvalidExclusion => "invalid exclusion duration",
+            RoulefiError::AlreadyReferred => "referrer already registered",
+            RoulefiError::InvalidReferrer => "invalid referrer",
+            RoulefiError::InvalidReferralPolicy => "invalid referral policy",
+            RoulefiError::JackpotClosed => "jackpot closed",
+            RoulefiError::InvalidJackpotRules => "invalid jackpot rules",
+            RoulefiError::StandingOrdersFull => "too many standing orders",
+            RoulefiError::StandingOrderNotFound => "standing order not found",
+            RoulefiError::InvalidStrategy => "invalid strategy",
+            RoulefiError::StrategyNotFound => "strategy not found",
+            RoulefiError::TournamentNotFound => "tournament not found",
+            RoulefiError::TournamentClosed => "tournament is closed",
+            RoulefiError::TournamentFull => "tournament is full",
+            RoulefiError::AlreadyInTournament => "already in a tournament",
+            RoulefiError::NotInTournament => "not in a tournament",
+            RoulefiError::NotEnoughTournamentChips => "not enough tournament chips",
+            RoulefiError::TournamentNotOver => "tournament is not over",
+            RoulefiError::TournamentRoundInPlay => "tournament bets are still in play",
+            RoulefiError::TournamentBetsPlaced => "bets of the round are paid with tournament chips",
+            RoulefiError::InvalidTournament => "invalid tournament",
+            RoulefiError::InvalidStatsWindow => "invalid stats window",
+            RoulefiError::TableNotFound => "table not found",
+            RoulefiError::TableClosed => "table is closed",
+            RoulefiError::NotAllowlisted => "not on the allowlist of the table",
+            RoulefiError::HostOnly => "only the host of the table can call it",
+            RoulefiError::InvalidTable => "invalid table",
+            RoulefiError::TableRoundInPlay => "bets of the table are still in play",
+            RoulefiError::TableFeeNotPaid => "table creation fee not paid",
+        }
+    }
+}
+
+impl fmt::Display for RoulefiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "E{:03}: {}", self.code(), self.message())
+    }
+}
+
+pub fn fail(error: RoulefiError) -> ! {
+    env::panic(error.to_string().as_bytes())
+}
+
+pub fn require(condition: bool, error: RoulefiError) {
+    if !condition {
+        fail(error);
+    }
+}
+
+/*
+the non-panicking require, for checks whose failure is skipped instead of aborting the call
+*/
+pub fn ensure(condition: bool, error: RoulefiError) -> Result<(), RoulefiError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
+pub trait OrFail<T> {
+    fn or_fail(self, error: RoulefiError) -> T;
+}
+
+impl<T> OrFail<T> for Option<T> {
+    fn or_fail(self, error: RoulefiError) -> T {
+        self.unwrap_or_else(|| fail(error))
+    }
+}
+
+pub trait FailOnErr<T> {
+    fn or_fail(self) -> T;
+}
+
+impl<T> FailOnErr<T> for Result<T, RoulefiError> {
+    fn or_fail(self) -> T {
+        self.unwrap_or_else(|error| fail(error))
+    }
+}
+
+/*
+checked math for balance movements, an overflow or underflow fails with its own code instead of an opaque panic
+*/
+pub fn safe_add(a: u128, b: u128) -> u128 {
+    a.checked_add(b).or_fail(RoulefiError::Overflow)
+}
+
+pub fn safe_sub(a: u128, b: u128) -> u128 {
+    a.checked_sub(b).or_fail(RoulefiError::Underflow)
+}
+
+pub fn safe_mul(a: u128, b: u128) -> u128 {
+    a.checked_mul(b).or_fail(RoulefiError::Overflow)
+}
+
+/*
+checked math of the non-panicking checks, an overflow is returned as the error
+*/
+pub fn try_add(a: u128, b: u128) -> Result<u128, RoulefiError> {
+    a.checked_add(b).ok_or(RoulefiError::Overflow)
+}
+
+/*
+a * b / c without overflow of the product
+*/
+pub fn safe_mul_div(a: u128, b: u128, c: u128) -> u128 {
+    require(c > 0, RoulefiError::Overflow);
+    let result = U256::from(a) * U256::from(b) / U256::from(c);
+    require(result <= U256::from(u128::MAX), RoulefiError::Overflow);
+    result.as_u128()
+}
+
+/*
+block timestamp the given seconds after time, both timestamps in nanoseconds
+*/
+pub fn safe_time_after(time: u64, seconds: u64) -> u64 {
+    seconds.checked_mul(1000000000).and_then(|nanos| time.checked_add(nanos)).or_fail(RoulefiError::Overflow)
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    #[test]
+    fn error_message_with_code() {
+        assert_eq!(RoulefiError::NotEnoughBalance.to_string(), "E031: not enough balance");
+        assert_eq!(RoulefiError::OwnerOnly.to_string(), "E001: owner only");
+        assert_eq!(safe_mul_div(u128::MAX, 2, 4), u128::MAX / 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "E091: arithmetic underflow")]
+    fn sub_underflow() {
+        testing_env!(get_context());
+        safe_sub(1, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "E090: arithmetic overflow")]
+    fn add_overflow() {
+        testing_env!(get_context());
+        safe_add(u128::MAX, 1);
+    }
+}
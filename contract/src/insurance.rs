@@ -0,0 +1,32 @@
+use crate::*;
+
+impl Contract {
+    /*
+    the part of the round profit for insurance fund. the fund is topped up to insurance_cap,
+    and it stops taking profit when the pool is under water
+    */
+    pub(crate) fn cal_insurance_amount(&self, profit: u128) -> u128 {
+        if self.round_status.loss_amount > 0 {
+            return 0;
+        }
+        let amount = safe_mul_div(profit, self.config.insurance_rate as u128, 100);
+        std::cmp::min(amount, self.config.insurance_cap.saturating_sub(self.treasury_status.insurance_amount))
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+    when the pool is under water, anyone can move the insurance fund to stakes to cover the loss.
+    it is not pool profit, so the pool history and yields don't count it
+    */
+    pub fn replenish_pool(&mut self) -> U128 {
+        let amount = std::cmp::min(self.treasury_status.insurance_amount, self.round_status.loss_amount);
+        require(amount > 0, RoulefiError::NothingToReplenish);
+        self.treasury_status.insurance_amount = safe_sub(self.treasury_status.insurance_amount, amount);
+        self.distribute_pool(amount, true);
+        self.cal_max_amount_allowed();
+        self.assert_solvent();
+        U128::from(amount)
+    }
+}
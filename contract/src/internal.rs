@@ -1,38 +1,232 @@
 use crate::*;
+use crate::view::BetInfo;
+
+/*
+the share multiplier in percentage for a stake staked for staked_seconds.
+step_rate[i] is the bonus after step_time[i] seconds, Step takes the bonus of the last passed point,
+Linear interpolates between the points. no bonus before the first point and no more after the last one
+*/
+pub fn seniority_rate(step_time: &Vec<u64>, step_rate: &Vec<u32>, curve: &SeniorityCurve, staked_seconds: u64) -> u32 {
+    let mut bonus: u32 = 0;
+    for i in 0..step_time.len() {
+        if staked_seconds < step_time[i] {
+            if let SeniorityCurve::Linear = curve {
+                if i > 0 {
+                    let (start_time, end_time) = (step_time[i - 1], step_time[i]);
+                    let (start_rate, end_rate) = (step_rate[i - 1] as u64, step_rate[i] as u64);
+                    let elapsed = staked_seconds - start_time;
+                    bonus = if end_rate >= start_rate {
+                        (start_rate + (end_rate - start_rate) * elapsed / (end_time - start_time)) as u32
+                    } else {
+                        (start_rate - (start_rate - end_rate) * elapsed / (end_time - start_time)) as u32
+                    };
+                }
+            }
+            break;
+        }
+        bonus = step_rate[i];
+    }
+    100 + bonus
+}
 
 impl Contract {
-    pub(crate) fn cal_max_amount_allowed(&mut self) {
-        self.round_status.max_amount_allowed = (U256::from(self.round_status.stake_amount + self.round_status.profit_amount - self.round_status.loss_amount) * U256::from(self.config.amount_allowed_rate as u128) / U256::from(100 as u128)).as_u128();   //re-calculate max amount for bets
+    pub(crate) fn assert_owner(&self) {
+        require(env::predecessor_account_id() == self.owner_id, RoulefiError::OwnerOnly);
+    }
+
+    pub(crate) fn bet_close_block_index(&self) -> BlockHeight {
+        self.round_status.next_round_block_index.saturating_sub(self.config.bet_close_delta)
+    }
+
+    pub(crate) fn round_state(&self) -> RoundState {
+        let block_index = env::block_index();
+        if block_index > self.round_status.next_round_block_index {
+            RoundState::Spinning
+        } else if block_index > self.bet_close_block_index() {
+            RoundState::Closed
+        } else {
+            RoundState::Open
+        }
+    }
+
+    /*
+    bets of current round can be placed or changed until bet_close_delta blocks before the spin block
+    */
+    pub(crate) fn assert_bet_open(&self) {
+        require(self.round_state() == RoundState::Open, RoulefiError::BettingClosed);
+    }
+
+    /*
+    the first bet of a round nobody bet in time starts the countdown again, then the round must be open
+    */
+    pub(crate) fn internal_open_round(&mut self) {
+        if self.bet_accounts.len() == 0 && self.round_state() != RoundState::Open {
+            self.round_status.current_round_block_index = env::block_index();
+            self.round_status.next_round_block_index = env::block_index() + self.config.round_delta;
+        }
+        self.assert_bet_open();
+    }
+
+    /*
+    check the bets and move the chips from user's balance to the bets of current round
+    */
+    pub(crate) fn internal_place_bets(&mut self, account: &mut Account, bets: Vec<BetInfo>) {
+        self.try_place_bets(account, to_bets(&bets)).or_fail();
+    }
+
+    /*
+    place the bets if they all pass, nothing changes on error
+    */
+    pub(crate) fn try_place_bets(&mut self, account: &mut Account, bets: Vec<Bet>) -> Result<(), RoulefiError> {
+        let total = validate_bets(&self.config.table_limits, &account.bets, &bets)?;
+        ensure(account.balance >= total, RoulefiError::NotEnoughBalance)?;
+        self.check_pool_exposure(total)?;
+        self.round_status.bet_amount = safe_add(self.round_status.bet_amount, total);
+        account.balance -= total;  // the balance decrease when bet is confirmed
+        account.bets.extend(bets);
+        Ok(())
+    }
+
+    /*
+    the bets of current round and of the shared tables all play against the pool, together they stay below max_amount_allowed
+    */
+    pub(crate) fn check_pool_exposure(&self, amount: u128) -> Result<(), RoulefiError> {
+        let exposure = try_add(try_add(self.round_status.bet_amount, self.round_status.table_bet_amount)?, amount)?;
+        ensure(exposure < self.round_status.max_amount_allowed, RoulefiError::ExceedMaxAmountAllowed)
+    }
+
+    pub(crate) fn round_bet_chips(&self, account: &Account) -> u128 {
+        account.bets.iter().fold(0, |total, bet| safe_add(total, bet.chips))
+    }
+
+    /*
+    players with bets must keep gas_per_player in balance for the keeper fee
+    */
+    pub(crate) fn assert_gas_reserved(&self, account: &Account) {
+        require(account.balance >= self.config.gas_per_player, RoulefiError::GasNotReserved);
+    }
+
+    /*
+    give the chips of current round's bets back to user's balance
+    */
+    pub(crate) fn internal_refund_bets(&mut self, account_id: &AccountId, account: &mut Account) {
+        let total = self.round_bet_chips(account);
+        account.balance = safe_add(account.balance, total);
+        self.round_status.bet_amount = safe_sub(self.round_status.bet_amount, total);
+        account.bets.clear();
+        self.internal_unmark_strategy_placed(account_id);       // refunded bets are not settled by the strategy
+    }
+
+    /*
+    clear the bets and start the countdown of next round
+    */
+    pub(crate) fn internal_next_round(&mut self) {
+        self.bet_accounts.clear();
+        self.round_status.bet_amount = 0;
+        self.round_status.current_round_block_index = env::block_index();
+        self.round_status.next_round_block_index = env::block_index() + self.config.round_delta;
+        self.round_status.round_index += 1;
     }
 
-    pub(crate) fn cal_share(&self, stake: Stake) -> (U256, U256) {
-        let now = env::block_timestamp();
-        let time_delta = now - u64::from(stake.time);
-        let mut time_rate: u32 = 100;
-        for (i, time) in self.config.step_time.iter().enumerate() {
-            if time_delta > *time {
-                time_rate = 100 + self.config.step_rate[i];
+    pub(crate) fn remove_bet_account(&mut self, account_id: &AccountId) {
+        for i in 0..self.bet_accounts.len() {
+            if self.bet_accounts.get(i).unwrap() == *account_id {
+                self.bet_accounts.swap_remove(i);
                 break;
             }
         }
-        let share_numerator = U256::from(stake.amount + stake.profit - stake.loss) * U256::from(time_rate) / U256::from(100 as u128);
-        let share_denominator = U256::from(self.round_status.stake_amount + self.round_status.profit_amount - self.round_status.loss_amount);
-        (share_numerator, share_denominator)
+    }
+
+    pub(crate) fn cal_max_amount_allowed(&mut self) {
+        self.round_status.max_amount_allowed = safe_mul_div(self.pool_amount(), self.config.amount_allowed_rate as u128, 100);   //re-calculate max amount for bets
+    }
+
+    pub(crate) fn pool_amount(&self) -> u128 {
+        safe_sub(safe_add(self.round_status.stake_amount, self.round_status.profit_amount), self.round_status.loss_amount)
+    }
+
+    /*
+    the share multiplier of a stake in percentage
+    */
+    pub(crate) fn cal_time_rate(&self, stake: &Stake) -> u32 {
+        let staked_seconds = env::block_timestamp().saturating_sub(stake.time) / 1000000000;   // block timestamp is in nanoseconds
+        seniority_rate(&self.config.step_time, &self.config.step_rate, &self.config.seniority_curve, staked_seconds)
+    }
+
+    /*
+    record the pool profit and loss of today
+    */
+    pub(crate) fn record_pool_history(&mut self, profit: u128, loss: u128) {
+        let day = env::block_timestamp() / DAY_NANOS;
+        let mut pool_day = self.pool_history.get(&day).unwrap_or(PoolDay {
+            profit: 0,
+            loss: 0,
+            pool_amount: 0,
+        });
+        pool_day.profit = safe_add(pool_day.profit, profit);
+        pool_day.loss = safe_add(pool_day.loss, loss);
+        pool_day.pool_amount = self.pool_amount();
+        self.pool_history.insert(&day, &pool_day);
+    }
+
+    /*
+    a stake's weight in the pool is its value multiplied by the seniority rate
+    */
+    pub(crate) fn cal_stake_weight(&self, stake: &Stake) -> U256 {
+        U256::from(safe_sub(safe_add(stake.amount, stake.profit), stake.loss)) * U256::from(self.cal_time_rate(stake)) / U256::from(100 as u128)
+    }
+
+    pub(crate) fn cal_total_weight(&self) -> U256 {
+        let mut total_weight = U256::zero();
+        for account_id in self.stake_accounts.iter() {
+            let account = self.accounts.get(&account_id).unwrap_or(new_user());
+            for stake in account.stakes.iter() {
+                total_weight = total_weight + self.cal_stake_weight(stake);
+            }
+        }
+        total_weight
+    }
+
+    pub(crate) fn cal_share(&self, stake: &Stake, total_weight: U256) -> (U256, U256) {
+        (self.cal_stake_weight(stake), total_weight)
     }
     /*
     profit needs to calculate every round
     */
     pub(crate) fn cal_profit(&mut self, total_bet: u128, total_win:u128) {
-        let mut delta = 0;
         if total_bet >= total_win {
-            self.round_status.profit_amount += total_bet - total_win;
-            delta = total_bet - total_win;
-            let treasury_amount: u128 = (U256::from(delta) * U256::from(self.config.treasury_rate) / U256::from(100 as u128)).as_u128();   // deal with treasury
-            self.treasury_status.treasury_amount = self.treasury_status.treasury_amount + treasury_amount;
-            delta = delta - treasury_amount;
+            let mut delta = total_bet - total_win;
+            let treasury_amount = safe_mul_div(delta, self.config.treasury_rate as u128, 100);   // deal with treasury
+            self.treasury_status.treasury_amount = safe_add(self.treasury_status.treasury_amount, treasury_amount);
+            delta = safe_sub(delta, treasury_amount);
+            let insurance_amount = self.cal_insurance_amount(delta);   // top up insurance fund
+            self.treasury_status.insurance_amount = safe_add(self.treasury_status.insurance_amount, insurance_amount);
+            delta = safe_sub(delta, insurance_amount);
+            self.distribute_pool(delta, true);
+            self.record_pool_history(delta, 0);
         } else {
-            delta = total_win - total_bet;
-            self.round_status.loss_amount += total_win - total_bet;
+            let mut delta = total_win - total_bet;
+            let covered = std::cmp::min(delta, self.treasury_status.insurance_amount);   // insurance fund takes the loss first
+            self.treasury_status.insurance_amount = safe_sub(self.treasury_status.insurance_amount, covered);
+            delta = safe_sub(delta, covered);
+            self.distribute_pool(delta, false);
+            self.record_pool_history(0, delta);
+        }
+        self.cal_max_amount_allowed();
+    }
+
+    /*
+    share the profit or loss of the pool to every stake, the caller records it in the pool history if it is a pool result
+    */
+    pub(crate) fn distribute_pool(&mut self, delta: u128, is_profit: bool) {
+        if delta == 0 {
+            return;
+        }
+        let total_weight = self.cal_total_weight();   // shares are taken before the delta
+        if is_profit {
+            self.round_status.profit_amount = safe_add(self.round_status.profit_amount, delta);
+        } else {
+            self.round_status.loss_amount = safe_add(self.round_status.loss_amount, delta);
         }
 
         if self.round_status.profit_amount >= self.round_status.loss_amount {
@@ -43,15 +237,18 @@ impl Contract {
             self.round_status.profit_amount = 0;
         }
 
-        
-        for account_id in self.stake_accounts.iter() {                                           // deal with each stake
+        if total_weight.is_zero() {
+            return;
+        }
+        for account_id in self.stake_accounts.to_vec() {                                         // deal with each stake
             let mut account = self.accounts.get(&account_id).unwrap_or(new_user());
             for stake in account.stakes.iter_mut() {
-                let (share_numerator, share_denominator) = self.cal_share(stake.clone());
-                if total_bet < total_win {     
-                    stake.loss = (U256::from(delta) * share_numerator / share_denominator).as_u128();
+                let (share_numerator, share_denominator) = self.cal_share(stake, total_weight);
+                let share = (U256::from(delta) * share_numerator / share_denominator).as_u128();   // never above delta
+                if !is_profit {
+                    stake.loss = safe_add(stake.loss, share);
                 } else {
-                    stake.profit += (U256::from(delta) * share_numerator / share_denominator).as_u128();
+                    stake.profit = safe_add(stake.profit, share);
                 }
 
                 if stake.profit >= stake.loss {
@@ -61,11 +258,9 @@ impl Contract {
                     stake.loss -= stake.profit;
                     stake.profit = 0;
                 }
-                
+                self.internal_update_reward_weight(stake);     // the value and seniority of the stake moved
             }
             self.accounts.insert(&account_id, &account);
         }
-        self.cal_max_amount_allowed();
-    
     }
 }
\ No newline at end of file
@@ -0,0 +1,184 @@
+use crate::*;
+
+/*
+the progressive pot fed by jackpot side bets, it is kept apart from the staking pool
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone)]
+pub struct JackpotStatus {
+    pub pot_amount: u128,
+    pub last_number: Option<u8>,        // winning number of the last spin
+    pub last_payout: u128,              // amount of the last jackpot
+    pub last_winners: Vec<AccountId>,
+    pub last_payout_round: u64,
+}
+
+impl Contract {
+    /*
+    take the jackpot side bet from balance for current round
+    */
+    pub(crate) fn internal_enter_jackpot(&mut self, account: &mut Account) {
+        let chip = self.config.jackpot_chip;
+        require(chip > 0, RoulefiError::JackpotClosed);
+        require(account.balance >= chip, RoulefiError::NotEnoughBalance);
+        account.balance -= chip;
+        account.jackpot_chips = safe_add(account.jackpot_chips, chip);
+    }
+
+    pub(crate) fn internal_refund_jackpot(&mut self, account: &mut Account) {
+        account.balance = safe_add(account.balance, account.jackpot_chips);
+        account.jackpot_chips = 0;
+    }
+
+    /*
+    move the player's side bet to the pot and update the straight streak, returns whether the player hits the jackpot:
+    a straight bet hitting the winning number with a side bet, when the number repeats the last spin
+    or the player's straight bets have hit jackpot_streak rounds in a row. a round without the player breaks the streak
+    */
+    pub(crate) fn internal_settle_jackpot(&mut self, account: &mut Account, number: u8) -> bool {
+        let round_index = self.round_status.round_index;
+        let straight_hit = account.bets.iter().any(|bet| bet.bet_type == 5 && bet.number == number);
+        account.straight_streak = if !straight_hit {
+            0
+        } else if account.straight_streak > 0 && account.straight_round + 1 == round_index {
+            account.straight_streak + 1
+        } else {
+            1
+        };
+        if straight_hit {
+            account.straight_round = round_index;
+        }
+        if account.jackpot_chips == 0 {
+            return false;
+        }
+        self.jackpot.pot_amount = safe_add(self.jackpot.pot_amount, account.jackpot_chips);
+        account.jackpot_chips = 0;
+        let repeated = self.jackpot.last_number == Some(number);
+        straight_hit && (repeated || (self.config.jackpot_streak > 0 && account.straight_streak >= self.config.jackpot_streak))
+    }
+
+    /*
+    pay jackpot_payout_bps of the pot to the winners of the round in equal parts
+    */
+    pub(crate) fn internal_pay_jackpot(&mut self, winners: Vec<AccountId>, number: u8) {
+        self.jackpot.last_number = Some(number);
+        if winners.len() == 0 {
+            return;
+        }
+        let payout = safe_mul_div(self.jackpot.pot_amount, self.config.jackpot_payout_bps as u128, BPS_DENOMINATOR as u128);
+        let share = payout / winners.len() as u128;
+        for winner_id in winners.iter() {
+            let mut account = self.accounts.get(winner_id).or_fail(RoulefiError::AccountNotFound);
+            account.balance = safe_add(account.balance, share);
+            account.straight_streak = 0;      // a streak wins only once
+            self.accounts.insert(winner_id, &account);
+        }
+        self.jackpot.pot_amount = safe_sub(self.jackpot.pot_amount, share * winners.len() as u128);
+        self.jackpot.last_payout = share * winners.len() as u128;
+        self.jackpot.last_winners = winners;
+        self.jackpot.last_payout_round = self.round_status.round_index;
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    fn straight(number: u8) -> Bet {
+        Bet {
+            bet_type: 5,
+            number,
+            chips: CHIP_UNIT
+        }
+    }
+
+    #[test]
+    fn streak_hits_jackpot() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.jackpot.pot_amount = CHIP_UNIT * 1000;
+        let mut account = new_user();
+        account.balance = CHIP_UNIT * 100;
+
+        for number in [7, 8, 9].iter() {
+            account.bets = vec![straight(*number)];
+            contract.internal_enter_jackpot(&mut account);
+            let hit = contract.internal_settle_jackpot(&mut account, *number);
+            contract.jackpot.last_number = Some(*number);
+            assert_eq!(hit, account.straight_streak >= contract.config.jackpot_streak);
+            contract.round_status.round_index += 1;
+        }
+        assert_eq!(contract.jackpot.pot_amount, CHIP_UNIT * 1000 + contract.config.jackpot_chip * 3);
+
+        contract.accounts.insert(&"bob".to_string(), &account);
+        contract.internal_pay_jackpot(vec!["bob".to_string()], 9);
+        let payout = contract.jackpot.last_payout;
+        assert_eq!(payout, (CHIP_UNIT * 1000 + contract.config.jackpot_chip * 3) / 2);
+        let account = contract.accounts.get(&"bob".to_string()).unwrap();
+        assert_eq!(account.balance, CHIP_UNIT * 100 - contract.config.jackpot_chip * 3 + payout);
+        assert_eq!(account.straight_streak, 0);
+    }
+
+    #[test]
+    fn repeated_number_hits_jackpot() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        let mut account = new_user();
+        account.balance = CHIP_UNIT * 100;
+        contract.jackpot.last_number = Some(17);
+
+        account.bets = vec![straight(17)];
+        assert!(!contract.internal_settle_jackpot(&mut account, 17));   // no side bet
+        account.bets = vec![straight(17)];
+        contract.internal_enter_jackpot(&mut account);
+        assert!(contract.internal_settle_jackpot(&mut account, 17));
+    }
+
+    #[test]
+    fn skipped_round_breaks_streak() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        let mut account = new_user();
+        account.balance = CHIP_UNIT * 100;
+
+        account.bets = vec![straight(7)];
+        contract.internal_settle_jackpot(&mut account, 7);
+        contract.round_status.round_index += 1;
+        account.bets = vec![straight(8)];
+        contract.internal_settle_jackpot(&mut account, 8);
+        assert_eq!(account.straight_streak, 2);
+
+        contract.round_status.round_index += 2;      // bob sits out a round
+        account.bets = vec![straight(9)];
+        contract.internal_settle_jackpot(&mut account, 9);
+        assert_eq!(account.straight_streak, 1);
+    }
+}
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{Vector, LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::collections::{Vector, LookupMap, LookupSet, UnorderedMap, UnorderedSet};
 use near_sdk::serde::{Serialize, Deserialize};
 use near_sdk::{BlockHeight, Gas, PanicOnDefault, Promise, env, near_bindgen, BorshStorageKey};
 use near_sdk::{AccountId};
@@ -18,7 +18,35 @@ pub mod roulette;
 pub mod dealer;
 pub mod view;
 pub mod treasury;
+pub mod owner;
+pub mod airdrop;
+pub mod insurance;
+pub mod delegation;
+pub mod solvency;
+pub mod errors;
+pub mod limits;
+pub mod referral;
+pub mod jackpot;
+pub mod standing;
+pub mod strategy;
+pub mod tournament;
+pub mod stats;
+pub mod wheel;
+pub mod table;
 use crate::roulette::*;
+use crate::airdrop::TreasuryDrop;
+use crate::treasury::VestingGrant;
+use crate::delegation::DelegationStatus;
+use crate::errors::*;
+use crate::limits::GamingLimits;
+use crate::referral::ReferralStats;
+use crate::jackpot::JackpotStatus;
+use crate::standing::StandingOrder;
+use crate::strategy::Strategy;
+use crate::tournament::{Tournament, TournamentEntry};
+use crate::stats::{PlayerStats, Leaderboard};
+use crate::wheel::WheelStats;
+use crate::table::Table;
 
 construct_uint! {
     pub struct U256(4);
@@ -30,6 +58,13 @@ pub fn new_user() -> Account {
         balance: 0,
         stakes: Vec::new(),
         last_bet_time: 0,
+        treasury_epoch: 0,
+        treasury_volume: 0,
+        auto_compound: false,
+        jackpot_chips: 0,
+        straight_streak: 0,
+        straight_round: 0,
+        tournament: None,
     }
     
 }
@@ -57,27 +92,131 @@ pub struct Contract {
         number: number
     */
     owner_id: AccountId,
+    treasurer_id: AccountId,    // can publish treasury drops besides owner
     config: Config,
     round_status: RoundStatus,
     treasury_status: TreasuryStatus,
+    delegation: DelegationStatus,
+    jackpot: JackpotStatus,
+    wheel_stats: WheelStats,
 
     bet_accounts: Vector<AccountId>,  // users who have bets
     stake_accounts: UnorderedSet<AccountId>, // users who have stakes
     accounts: UnorderedMap<AccountId, Account>, // users data
+    treasury_epochs: LookupMap<u64, TreasuryEpoch>, // distributed treasury epochs
+    treasury_drops: Vector<TreasuryDrop>,   // treasury drops by merkle root
+    team_vestings: Vector<VestingGrant>,    // team shares of every distribution
+    pool_history: LookupMap<u64, PoolDay>,  // pool profit and loss by day
+    drop_claims: LookupSet<String>,         // "drop_id:account_id" already claimed
+    gaming_limits: LookupMap<AccountId, GamingLimits>,  // responsible gaming controls set by users
+    referrers: LookupMap<AccountId, AccountId>,         // player to referrer
+    referral_stats: LookupMap<AccountId, ReferralStats>,    // by referrer
+    standing_orders: LookupMap<AccountId, StandingOrder>,   // bets repeated every round
+    standing_accounts: UnorderedSet<AccountId>,         // users who have standing orders
+    strategies: LookupMap<AccountId, Strategy>,         // progressions run on standing orders
+    tournaments: Vector<Tournament>,
+    player_stats: LookupMap<AccountId, PlayerStats>,   // lifetime and recent days
+    tables: Vector<Table>,                      // private tables
+    leaderboards: LookupMap<u64, Leaderboard>,  // top players by day, and of all time at ALL_TIME
+    leaderboard_days: Vec<u64>,                 // days with a leaderboard, oldest first
+    table_wheel_stats: LookupMap<u64, WheelStats>,  // by private table, kept apart from the main wheel
+    epoch_drops: LookupMap<u64, u64>,           // treasury drop id by treasury epoch
     
 }
 
+/*
+pool profit and loss of a day, after treasury and insurance
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug)]
+pub struct PoolDay {
+    profit: u128,
+    loss: u128,
+    pool_amount: u128,          // pool amount after the last round of the day
+}
+
 #[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
 pub struct Config {
     treasury_threshold: u128,   // treasury amount threshold
-    treasury_shares: Vec<u32>,   // treasury shares
+    treasury_shares: Vec<TreasuryShare>,   // treasury beneficiaries, shares add up to 10000 basis points
+    team_vesting: VestingConfig,    // team shares are released by vesting
+    player_volume_cap: u128,    // max bet volume of a player counted in a treasury epoch, 0 for no cap
     min_lock_time: u32,         // min time for staking pool to withdraw
-    step_time: Vec<u64>,        // a user's share in the pool will rise after each step_time
-    step_rate: Vec<u32>,        // the share multiplier for each step_time
+    step_time: Vec<u64>,        // a user's share in the pool will rise after each step_time, in seconds, ascending
+    step_rate: Vec<u32>,        // the share bonus in percentage for each step_time
+    seniority_curve: SeniorityCurve,   // how the bonus goes between step_time points
     treasury_rate: u32,         // the percentage for every round profit in the pool
+    insurance_rate: u32,        // the percentage for every round profit to insurance fund, after treasury
+    insurance_cap: u128,        // insurance fund stops taking profit above insurance_cap
+    validators: Vec<AccountId>, // validator pools allowed for delegation
+    delegate_rate: u32,         // the percentage of pool liquidity above max_amount_allowed to delegate, 0 for no delegation
     amount_allowed_rate: u32,   // max_amount_allowed = (stake_amount + profit_amount) * amount_allowed_rate
-    gas_per_player: u128,
-    round_delta: u64
+    gas_per_player: u128,       // keeper fee charged from every player of the round, paid to the caller of spin_wheel
+    round_delta: u64,
+    bet_close_delta: u64,       // no more bets within bet_close_delta blocks before next_round_block_index
+    refund_delay: u64,          // bets can be refunded if the round is not spun refund_delay blocks after next_round_block_index
+    table_limits: TableLimits,
+    limit_cooldown: u64,        // a looser gaming limit works limit_cooldown seconds after it is set
+    referral_tier_bps: Vec<u32>,    // share of house profit from a referred player for each tier of referrers, in basis points
+    referral_max_tiers: u32,    // max tiers of referrers paid
+    jackpot_chip: u128,         // price of a jackpot side bet, 0 to close the jackpot
+    jackpot_payout_bps: u32,    // part of the pot paid when it is hit, in basis points
+    jackpot_streak: u32,        // straight bets hitting this many rounds in a row hit the jackpot, 0 to disable
+    table_creation_fee: u128,   // paid to treasury by hosts other than owner to create a private table
+    wheel_window: u32,          // recent spins kept for the hot and cold numbers
+}
+
+/*
+who takes a part of the treasury
+*/
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Beneficiary {
+    Players,                    // claimed by players in proportion to bet volume
+    Stakers,                    // claimed by stake users in proportion to stake amount
+    Team,                       // released to owner by vesting
+    Insurance,                  // insurance fund
+    ReferralPool,               // referral pool
+    Account(AccountId),         // credited to the account's balance
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug, Clone)]
+pub struct TreasuryShare {
+    pub beneficiary: Beneficiary,
+    pub share_bps: u32,         // basis points of the treasury
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeniorityCurve {
+    Step,                       // bonus of the last passed step_time
+    Linear,                     // linear interpolation between step_time points
+}
+
+/*
+linear vesting with a cliff, in seconds
+*/
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug, Clone)]
+pub struct VestingConfig {
+    pub cliff: u64,             // nothing is released before cliff
+    pub duration: u64,          // all released after duration
+}
+
+/*
+limits for every bet item, min_chips and max_chips are indexed by bet_type
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone)]
+pub struct TableLimits {
+    pub min_chips: Vec<u128>,   // min chips for a bet item
+    pub max_chips: Vec<u128>,   // max chips for a bet item
+    pub chip_unit: u128,        // chips must be a multiple of chip_unit, 1 chip = 0.01 NEAR
 }
 
 /*
@@ -94,17 +233,54 @@ pub struct RoundStatus {
     spinning: bool,
     max_amount_allowed: u128,
     bet_amount: u128,
+    table_bet_amount: u128,     // open bets of shared private tables, they play against the same pool
     stake_amount: u128,
     profit_amount: u128,
     loss_amount: u128,
 }
 
+/*
+phase of current round
+  Open: accepting bets
+  Closed: no more bets, waiting for the spin block
+  Spinning: spin block passed, waiting for spin_wheel
+*/
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug, PartialEq)]
+pub enum RoundState {
+    Open,
+    Closed,
+    Spinning,
+}
+
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
 #[serde(crate = "near_sdk::serde")]
 #[derive(Debug)]
 pub struct TreasuryStatus {
     last_treasury_time: u64,
     treasury_amount: u128,
+    epoch_index: u64,           // players who bet in this epoch share the next treasury
+    epoch_player_count: u64,    // players who bet in this epoch
+    epoch_volume: u128,         // total counted bet volume of this epoch
+    epoch_volume_cap: u128,     // player_volume_cap of this epoch
+    insurance_amount: u128,     // insurance fund
+    referral_pool_amount: u128, // referral pool
+    vesting_amount: u128,       // team shares not released yet
+    stake_reward_index: u128,   // accumulated treasury reward for each unit of stake weight, multiplied by REWARD_INDEX_PRECISION
+    stake_weight: u128,         // sum of the stakes' reward weights
+    unclaimed_amount: u128,     // treasury distributed but not claimed yet
+}
+
+/*
+players' share of a distributed treasury epoch, claimed by players lazily in proportion to their bet volume
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug)]
+pub struct TreasuryEpoch {
+    player_amount: u128,        // players' share of the epoch
+    volume: u128,               // total counted bet volume of the epoch
+    volume_cap: u128,           // max bet volume of a player counted in the epoch
 }
 
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
@@ -114,44 +290,104 @@ pub struct Account {
     bets: Vec<Bet>,               // all bets
     balance: u128,                // user deposit in the contract
     stakes: Vec<Stake>,           // all stakes
-    last_bet_time: u64
+    last_bet_time: u64,
+    treasury_epoch: u64,          // the treasury epoch the user last bet in, 0 if claimed
+    treasury_volume: u128,        // bet volume in treasury_epoch
+    auto_compound: bool,          // treasury rewards go to a new stake
+    jackpot_chips: u128,          // jackpot side bet of current round
+    straight_streak: u32,         // rounds in a row the user's straight bets hit
+    straight_round: u64,          // round index of the last straight hit
+    tournament: Option<TournamentEntry>,  // the tournament the user is playing
 }
 
 #[derive(BorshSerialize, BorshStorageKey)]
 enum StorageKey {
     BetAccounts,
     StakeAccounts,
-    Accounts
+    Accounts,
+    TreasuryEpochs,
+    TreasuryDrops,
+    DropClaims,
+    TeamVestings,
+    PoolHistory,
+    GamingLimits,
+    Referrers,
+    ReferralStats,
+    StandingOrders,
+    StandingAccounts,
+    Strategies,
+    Tournaments,
+    PlayerStats,
+    Tables,
+    Leaderboards,
+    TableWheelStats,
+    EpochDrops
 }
 
 const PAYOUTS: [u8; 6] = [2,3,3,2,2,36];
 const NUMBER_RANGE: [u8; 6] = [1,2,2,1,1,36];
+const CHIP_UNIT: u128 = 10000000000000000000000;   // 0.01 NEAR
+const REWARD_INDEX_PRECISION: u128 = 1000000000000;
+const BPS_DENOMINATOR: u32 = 10000;
+const DAY_NANOS: u64 = 86400000000000;
 
 #[near_bindgen]
 impl Contract {
     #[init]
     pub fn new() -> Self {
-        assert!(env::state_read::<Self>().is_none(), "Already initialized");
+        require(env::state_read::<Self>().is_none(), RoulefiError::AlreadyInitialized);
         let this = Self {
             owner_id: env::predecessor_account_id(),
+            treasurer_id: env::predecessor_account_id(),
             config: Config {
                 amount_allowed_rate: 10,
                 min_lock_time: 0,                       // now it is not working
                 step_time: vec![0, 604800, 2592000],    // one week , one month
                 step_rate: vec![0, 5, 20],        // 5%, 20%
+                seniority_curve: SeniorityCurve::Step,
                 treasury_rate: 10,
+                insurance_rate: 10,
+                insurance_cap: 1000000000000000000000000000,   // 1k
+                validators: Vec::new(),
+                delegate_rate: 0,
                 treasury_threshold: 10000000000000000000000000000,  // 10k
-                treasury_shares: vec![40, 40, 20],    // gamers, stake users, team
+                treasury_shares: vec![
+                    TreasuryShare { beneficiary: Beneficiary::Players, share_bps: 4000 },
+                    TreasuryShare { beneficiary: Beneficiary::Stakers, share_bps: 4000 },
+                    TreasuryShare { beneficiary: Beneficiary::Team, share_bps: 2000 },
+                ],
+                team_vesting: VestingConfig {
+                    cliff: 2592000,             // one month
+                    duration: 31536000,         // one year
+                },
+                player_volume_cap: 0,
                 gas_per_player: 10000000000000000000000,
                 round_delta: 60,
+                bet_close_delta: 5,
+                refund_delay: 1000,
+                table_limits: TableLimits {
+                    min_chips: vec![CHIP_UNIT; 6],                                          // 0.01 NEAR for every bet type
+                    max_chips: vec![CHIP_UNIT * 10000, CHIP_UNIT * 10000, CHIP_UNIT * 10000,
+                                    CHIP_UNIT * 10000, CHIP_UNIT * 10000, CHIP_UNIT * 1000],   // 100 NEAR, 10 NEAR for number
+                    chip_unit: CHIP_UNIT,
+                },
+                limit_cooldown: 604800,                 // one week
+                referral_tier_bps: vec![500],           // 5% for the direct referrer
+                referral_max_tiers: 1,
+                jackpot_chip: CHIP_UNIT * 10,          // 0.1 NEAR
+                jackpot_payout_bps: 5000,               // half of the pot
+                jackpot_streak: 3,
+                table_creation_fee: CHIP_UNIT * 1000,   // 10 NEAR
+                wheel_window: 200,
             },
             round_status: RoundStatus {
                 round_index: 0,
                 current_round_block_index: env::block_index(),
-                next_round_block_index: 0,             
+                next_round_block_index: env::block_index() + 60,
                 last_round_win_number: 0,
                 max_amount_allowed: 0, 
                 bet_amount: 0,
+                table_bet_amount: 0,
                 stake_amount: 0,
                 profit_amount: 0,
                 loss_amount: 0,
@@ -160,10 +396,54 @@ impl Contract {
             treasury_status: TreasuryStatus {
                 last_treasury_time: 0,
                 treasury_amount: 0,
+                epoch_index: 1,
+                epoch_player_count: 0,
+                epoch_volume: 0,
+                epoch_volume_cap: 0,
+                insurance_amount: 0,
+                referral_pool_amount: 0,
+                vesting_amount: 0,
+                stake_reward_index: 0,
+                stake_weight: 0,
+                unclaimed_amount: 0,
+            },
+            delegation: DelegationStatus {
+                validator_id: None,
+                staked_amount: 0,
+                unstaked_amount: 0,
+                unstake_epoch: 0,
+                pending_amount: 0,
+                pending: false,
+            },
+            wheel_stats: WheelStats::new(),
+            jackpot: JackpotStatus {
+                pot_amount: 0,
+                last_number: None,
+                last_payout: 0,
+                last_winners: Vec::new(),
+                last_payout_round: 0,
             },
             bet_accounts: Vector::new(StorageKey::BetAccounts),
             stake_accounts: UnorderedSet::new(StorageKey::StakeAccounts),
             accounts: UnorderedMap::new(StorageKey::Accounts),
+            treasury_epochs: LookupMap::new(StorageKey::TreasuryEpochs),
+            treasury_drops: Vector::new(StorageKey::TreasuryDrops),
+            team_vestings: Vector::new(StorageKey::TeamVestings),
+            pool_history: LookupMap::new(StorageKey::PoolHistory),
+            drop_claims: LookupSet::new(StorageKey::DropClaims),
+            gaming_limits: LookupMap::new(StorageKey::GamingLimits),
+            referrers: LookupMap::new(StorageKey::Referrers),
+            referral_stats: LookupMap::new(StorageKey::ReferralStats),
+            standing_orders: LookupMap::new(StorageKey::StandingOrders),
+            standing_accounts: UnorderedSet::new(StorageKey::StandingAccounts),
+            strategies: LookupMap::new(StorageKey::Strategies),
+            tournaments: Vector::new(StorageKey::Tournaments),
+            player_stats: LookupMap::new(StorageKey::PlayerStats),
+            tables: Vector::new(StorageKey::Tables),
+            leaderboards: LookupMap::new(StorageKey::Leaderboards),
+            leaderboard_days: Vec::new(),
+            table_wheel_stats: LookupMap::new(StorageKey::TableWheelStats),
+            epoch_drops: LookupMap::new(StorageKey::EpochDrops),
 
         };
         this
@@ -196,8 +476,8 @@ mod tests {
             predecessor_account_id: "bob".to_string(),
             input: vec![],
             block_index: 0,
-            block_timestamp: env::block_timestamp() - 100,
-            account_balance: 100000000000000000,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
             account_locked_balance: 0,
             storage_usage: 10u64.pow(6),
             attached_deposit: 10000000000000000,
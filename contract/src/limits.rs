@@ -0,0 +1,378 @@
+use crate::*;
+
+const WEEK_NANOS: u64 = DAY_NANOS * 7;
+const MONTH_NANOS: u64 = DAY_NANOS * 30;
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitKind {
+    Deposit,                    // deposits to balance and stakes
+    Loss,                       // net loss of settled bets
+    Wager,                      // chips placed on bets and stakes from balance
+}
+
+/*
+caps of a limit kind, 0 for no limit
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeriodLimits {
+    pub daily: u128,
+    pub weekly: u128,
+    pub monthly: u128,
+}
+
+/*
+amount used in the current day, week and month. windows are fixed, counted from the unix epoch
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, Default)]
+pub struct PeriodUsage {
+    pub day: u64,
+    pub daily: u128,
+    pub week: u64,
+    pub weekly: u128,
+    pub month: u64,
+    pub monthly: u128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, Default)]
+pub struct LimitState {
+    pub limits: PeriodLimits,
+    pub pending_limits: PeriodLimits,   // limits after the cooldown when some are loosened
+    pub pending_time: u64,              // 0 if nothing is pending
+    pub usage: PeriodUsage,
+}
+
+/*
+responsible gaming controls of an account, usage is counted from the time the first limit is set
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, Default)]
+pub struct GamingLimits {
+    pub deposit: LimitState,
+    pub loss: LimitState,
+    pub wager: LimitState,
+    pub exclusion_end: u64,     // no deposits, bets or stakes before exclusion_end
+}
+
+/*
+the stricter of two caps where 0 is no limit
+*/
+pub fn tighter(a: u128, b: u128) -> u128 {
+    if a == 0 {
+        b
+    } else if b == 0 {
+        a
+    } else {
+        std::cmp::min(a, b)
+    }
+}
+
+fn within(limit: u128, amount: u128) -> bool {
+    limit == 0 || amount <= limit
+}
+
+impl PeriodUsage {
+    pub fn roll(&mut self, now: u64) {
+        if self.day != now / DAY_NANOS {
+            self.day = now / DAY_NANOS;
+            self.daily = 0;
+        }
+        if self.week != now / WEEK_NANOS {
+            self.week = now / WEEK_NANOS;
+            self.weekly = 0;
+        }
+        if self.month != now / MONTH_NANOS {
+            self.month = now / MONTH_NANOS;
+            self.monthly = 0;
+        }
+    }
+
+    pub fn fits(&self, limits: &PeriodLimits, amount: u128) -> bool {
+        within(limits.daily, self.daily.saturating_add(amount))        // a saturated usage is above any cap
+            && within(limits.weekly, self.weekly.saturating_add(amount))
+            && within(limits.monthly, self.monthly.saturating_add(amount))
+    }
+
+    pub fn add(&mut self, amount: u128) {
+        self.daily = safe_add(self.daily, amount);
+        self.weekly = safe_add(self.weekly, amount);
+        self.monthly = safe_add(self.monthly, amount);
+    }
+
+    pub fn sub(&mut self, amount: u128) {
+        self.daily = self.daily.saturating_sub(amount);
+        self.weekly = self.weekly.saturating_sub(amount);
+        self.monthly = self.monthly.saturating_sub(amount);
+    }
+}
+
+impl LimitState {
+    /*
+    apply the pending limits after the cooldown and start new windows
+    */
+    pub fn refresh(&mut self, now: u64) {
+        if self.pending_time > 0 && now >= self.pending_time {
+            self.limits = self.pending_limits.clone();
+            self.pending_time = 0;
+        }
+        self.usage.roll(now);
+    }
+
+    /*
+    a tighter cap works at once, a looser one at pending_time
+    */
+    pub fn update(&mut self, limits: PeriodLimits, pending_time: u64) {
+        let tightened = PeriodLimits {
+            daily: tighter(limits.daily, self.limits.daily),
+            weekly: tighter(limits.weekly, self.limits.weekly),
+            monthly: tighter(limits.monthly, self.limits.monthly),
+        };
+        if tightened == limits {
+            self.pending_time = 0;
+        } else {
+            self.pending_limits = limits;
+            self.pending_time = pending_time;
+        }
+        self.limits = tightened;
+    }
+}
+
+impl GamingLimits {
+    pub fn state(&self, kind: LimitKind) -> &LimitState {
+        match kind {
+            LimitKind::Deposit => &self.deposit,
+            LimitKind::Loss => &self.loss,
+            LimitKind::Wager => &self.wager,
+        }
+    }
+
+    pub fn state_mut(&mut self, kind: LimitKind) -> &mut LimitState {
+        match kind {
+            LimitKind::Deposit => &mut self.deposit,
+            LimitKind::Loss => &mut self.loss,
+            LimitKind::Wager => &mut self.wager,
+        }
+    }
+
+    pub fn refresh(&mut self, now: u64) {
+        self.deposit.refresh(now);
+        self.loss.refresh(now);
+        self.wager.refresh(now);
+    }
+}
+
+fn limit_error(kind: LimitKind) -> RoulefiError {
+    match kind {
+        LimitKind::Deposit => RoulefiError::ExceedDepositLimit,
+        LimitKind::Loss => RoulefiError::ExceedLossLimit,
+        LimitKind::Wager => RoulefiError::ExceedWagerLimit,
+    }
+}
+
+impl Contract {
+    pub(crate) fn check_not_excluded(&self, account_id: &AccountId) -> Result<(), RoulefiError> {
+        match self.gaming_limits.get(account_id) {
+            Some(limits) => ensure(env::block_timestamp() >= limits.exclusion_end, RoulefiError::SelfExcluded),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn assert_not_excluded(&self, account_id: &AccountId) {
+        self.check_not_excluded(account_id).or_fail();
+    }
+
+    /*
+    check the amount against the account's caps of the kind without counting it
+    */
+    pub(crate) fn check_limit(&self, account_id: &AccountId, kind: LimitKind, amount: u128) -> Result<(), RoulefiError> {
+        match self.gaming_limits.get(account_id) {
+            Some(mut limits) => {
+                limits.refresh(env::block_timestamp());
+                let state = limits.state(kind);
+                ensure(state.usage.fits(&state.limits, amount), limit_error(kind))
+            },
+            None => Ok(()),
+        }
+    }
+
+    /*
+    check the amount against the account's caps of the kind and count it
+    */
+    pub(crate) fn internal_use_limit(&mut self, account_id: &AccountId, kind: LimitKind, amount: u128) {
+        if amount == 0 {
+            return;
+        }
+        self.check_limit(account_id, kind, amount).or_fail();
+        if let Some(mut limits) = self.gaming_limits.get(account_id) {
+            limits.refresh(env::block_timestamp());
+            limits.state_mut(kind).usage.add(amount);
+            self.gaming_limits.insert(account_id, &limits);
+        }
+    }
+
+    /*
+    the chips of open bets can all be lost, they must fit in the loss caps
+    */
+    pub(crate) fn assert_loss_limit(&self, account_id: &AccountId, account: &Account) {
+        self.check_limit(account_id, LimitKind::Loss, self.round_bet_chips(account)).or_fail();
+    }
+
+    /*
+    count the net result of the player's settled bets, a win takes back the loss of the same windows
+    */
+    pub(crate) fn internal_record_loss(&mut self, account_id: &AccountId, bet_amount: u128, win_amount: u128) {
+        if let Some(mut limits) = self.gaming_limits.get(account_id) {
+            limits.refresh(env::block_timestamp());
+            if bet_amount > win_amount {
+                limits.loss.usage.add(bet_amount - win_amount);
+            } else {
+                limits.loss.usage.sub(win_amount - bet_amount);
+            }
+            self.gaming_limits.insert(account_id, &limits);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+    set the daily, weekly and monthly caps of a kind on the caller's account, 0 for no limit.
+    tighter caps work at once, looser ones after limit_cooldown
+    */
+    pub fn set_gaming_limits(&mut self, kind: LimitKind, daily: U128, weekly: U128, monthly: U128) {
+        let sender_id = env::predecessor_account_id();
+        let now = env::block_timestamp();
+        let mut limits = self.gaming_limits.get(&sender_id).unwrap_or_default();
+        limits.refresh(now);
+        limits.state_mut(kind).update(PeriodLimits {
+            daily: daily.into(),
+            weekly: weekly.into(),
+            monthly: monthly.into(),
+        }, safe_time_after(now, self.config.limit_cooldown));
+        self.gaming_limits.insert(&sender_id, &limits);
+        self.assert_solvent();
+    }
+
+    /*
+    no deposits, bets or stakes for duration seconds, an exclusion can be extended but not shortened
+    */
+    pub fn self_exclude(&mut self, duration: U64) {
+        let sender_id = env::predecessor_account_id();
+        let duration = u64::from(duration);
+        require(duration > 0, RoulefiError::InvalidExclusion);
+        let mut limits = self.gaming_limits.get(&sender_id).unwrap_or_default();
+        let exclusion_end = safe_time_after(env::block_timestamp(), duration);
+        limits.exclusion_end = std::cmp::max(limits.exclusion_end, exclusion_end);
+        self.gaming_limits.insert(&sender_id, &limits);
+        self.assert_solvent();
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+    use crate::view::BetInfo;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    fn red(chips: u128) -> Vec<BetInfo> {
+        vec![BetInfo {
+            bet_type: 0,
+            number: 1,
+            chips: U128::from(chips)
+        }]
+    }
+
+    #[test]
+    #[should_panic(expected = "exceed deposit limit")]
+    fn deposit_over_daily_limit() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.set_gaming_limits(LimitKind::Deposit, U128::from(CHIP_UNIT * 100), U128::from(0), U128::from(0));
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 60;
+        testing_env!(context);
+        contract.deposit();
+        contract.deposit();
+    }
+
+    #[test]
+    fn loosen_limit_after_cooldown() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.set_gaming_limits(LimitKind::Wager, U128::from(CHIP_UNIT * 100), U128::from(0), U128::from(0));
+        contract.set_gaming_limits(LimitKind::Wager, U128::from(CHIP_UNIT * 50), U128::from(0), U128::from(0));
+        assert_eq!(contract.gaming_limits.get(&"bob".to_string()).unwrap().wager.limits.daily, CHIP_UNIT * 50);
+
+        contract.set_gaming_limits(LimitKind::Wager, U128::from(0), U128::from(0), U128::from(0));
+        let limits = contract.gaming_limits.get(&"bob".to_string()).unwrap();
+        assert_eq!(limits.wager.limits.daily, CHIP_UNIT * 50);
+        assert_eq!(limits.wager.pending_time, 1600000000000000000 + contract.config.limit_cooldown * 1000000000);
+
+        let mut context = get_context();
+        context.block_timestamp += contract.config.limit_cooldown * 1000000000;
+        testing_env!(context);
+        let mut limits = contract.gaming_limits.get(&"bob".to_string()).unwrap();
+        limits.refresh(env::block_timestamp());
+        assert_eq!(limits.wager.limits.daily, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "self excluded")]
+    fn self_excluded_bet() {
+        let mut context = get_context();
+        context.predecessor_account_id = "alice".to_string();
+        context.attached_deposit = CHIP_UNIT * 100000;
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.stake();
+
+        let context = get_context();
+        testing_env!(context);
+        contract.self_exclude(U64::from(86400));
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.bet(red(CHIP_UNIT * 10), U64::from(0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "E090: arithmetic overflow")]
+    fn exclusion_overflow() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.self_exclude(U64::from(u64::MAX / 1000));
+    }
+}
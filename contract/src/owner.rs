@@ -1,6 +1,190 @@
+use crate::*;
+use near_sdk::json_types::ValidAccountId;
+use crate::wheel::MAX_WHEEL_WINDOW;
+use crate::roulette::MIN_REFUND_DELAY;
+
 #[near_bindgen]
 impl Contract {
-    pub fn get_status(&self) -> AccountStatusInfo {
-        
+
+    /*
+    treasurer can publish treasury drops
+    */
+    pub fn set_treasurer(&mut self, treasurer_id: ValidAccountId) {
+        self.assert_owner();
+        self.treasurer_id = treasurer_id.into();
+        self.assert_solvent();
     }
-}
\ No newline at end of file
+
+    /*
+    set the treasury beneficiaries, shares must add up to 10000 basis points
+    */
+    pub fn set_treasury_shares(&mut self, shares: Vec<TreasuryShare>) {
+        self.assert_owner();
+        let mut total: u128 = 0;
+        for (i, share) in shares.iter().enumerate() {
+            total = safe_add(total, share.share_bps as u128);
+            if let Beneficiary::Account(_) = share.beneficiary {
+                continue;
+            }
+            require(shares[..i].iter().all(|other| other.beneficiary != share.beneficiary), RoulefiError::DuplicatedBeneficiary);
+        }
+        require(total == BPS_DENOMINATOR as u128, RoulefiError::InvalidShares);
+        self.config.treasury_shares = shares;
+        self.assert_solvent();
+    }
+
+    /*
+    set the vesting of team shares, in seconds
+    */
+    pub fn set_team_vesting(&mut self, cliff: U64, duration: U64) {
+        self.assert_owner();
+        require(u64::from(cliff) <= u64::from(duration), RoulefiError::InvalidVesting);
+        self.config.team_vesting = VestingConfig {
+            cliff: cliff.into(),
+            duration: duration.into(),
+        };
+        self.assert_solvent();
+    }
+
+    /*
+    insurance_rate is the percentage of round profit after treasury, the fund is topped up to insurance_cap
+    */
+    pub fn set_insurance_policy(&mut self, insurance_rate: u32, insurance_cap: U128) {
+        self.assert_owner();
+        require(insurance_rate <= 100, RoulefiError::InvalidInsuranceRate);
+        self.config.insurance_rate = insurance_rate;
+        self.config.insurance_cap = insurance_cap.into();
+        self.assert_solvent();
+    }
+
+    /*
+    step_time in seconds must be ascending, step_rate is the share bonus in percentage for each step_time
+    */
+    pub fn set_seniority_schedule(&mut self, step_time: Vec<U64>, step_rate: Vec<u32>, curve: SeniorityCurve) {
+        self.assert_owner();
+        require(step_time.len() > 0 && step_time.len() == step_rate.len(), RoulefiError::InvalidSeniority);
+        let step_time: Vec<u64> = step_time.into_iter().map(|time| u64::from(time)).collect();
+        for i in 1..step_time.len() {
+            require(step_time[i] > step_time[i - 1], RoulefiError::SeniorityNotAscending);
+        }
+        self.config.step_time = step_time;
+        self.config.step_rate = step_rate;
+        self.config.seniority_curve = curve;
+        self.assert_solvent();
+    }
+
+    /*
+    validator pools allowed for delegation, delegate_rate is the percentage of pool liquidity above max_amount_allowed to delegate
+    */
+    pub fn set_delegation_policy(&mut self, validators: Vec<ValidAccountId>, delegate_rate: u32) {
+        self.assert_owner();
+        require(delegate_rate <= 100, RoulefiError::InvalidDelegateRate);
+        self.config.validators = validators.into_iter().map(|validator_id| validator_id.into()).collect();
+        self.config.delegate_rate = delegate_rate;
+        self.assert_solvent();
+    }
+
+    /*
+    max bet volume of a player counted in a treasury epoch, 0 for no cap. it works from the next epoch
+    */
+    pub fn set_player_volume_cap(&mut self, volume_cap: U128) {
+        self.assert_owner();
+        self.config.player_volume_cap = u128::from(volume_cap);
+        self.assert_solvent();
+    }
+
+    /*
+    set min/max chips for each bet type and the chip unit
+    */
+    pub fn set_table_limits(&mut self, min_chips: Vec<U128>, max_chips: Vec<U128>, chip_unit: U128) {
+        self.assert_owner();
+        require(min_chips.len() == PAYOUTS.len() && max_chips.len() == PAYOUTS.len(), RoulefiError::InvalidLimitsLength);
+        let chip_unit = u128::from(chip_unit);
+        require(chip_unit > 0, RoulefiError::InvalidChipUnit);
+        let min_chips: Vec<u128> = min_chips.into_iter().map(|chips| u128::from(chips)).collect();
+        let max_chips: Vec<u128> = max_chips.into_iter().map(|chips| u128::from(chips)).collect();
+        for i in 0..min_chips.len() {
+            require(min_chips[i] > 0 && min_chips[i] <= max_chips[i], RoulefiError::InvalidLimits);
+            require(min_chips[i] % chip_unit == 0 && max_chips[i] % chip_unit == 0, RoulefiError::InvalidChips);
+        }
+        self.config.table_limits = TableLimits {
+            min_chips,
+            max_chips,
+            chip_unit,
+        };
+        self.assert_solvent();
+    }
+
+    /*
+    cooldown in seconds before a looser gaming limit works, it applies to limits set from now on
+    */
+    pub fn set_limit_cooldown(&mut self, cooldown: U64) {
+        self.assert_owner();
+        self.config.limit_cooldown = u64::from(cooldown);
+        self.assert_solvent();
+    }
+
+    /*
+    referrers of a player share tier_bps[i] of the house profit from the player's bets, up to max_tiers tiers
+    */
+    pub fn set_referral_policy(&mut self, tier_bps: Vec<u32>, max_tiers: u32) {
+        self.assert_owner();
+        require(tier_bps.len() as u32 <= max_tiers, RoulefiError::InvalidReferralPolicy);
+        require(tier_bps.iter().sum::<u32>() <= BPS_DENOMINATOR, RoulefiError::InvalidReferralPolicy);
+        self.config.referral_tier_bps = tier_bps;
+        self.config.referral_max_tiers = max_tiers;
+        self.assert_solvent();
+    }
+
+    /*
+    price of a jackpot side bet (0 closes the jackpot), part of the pot paid when it is hit and the straight streak to hit it
+    */
+    pub fn set_jackpot_rules(&mut self, jackpot_chip: U128, payout_bps: u32, streak: u32) {
+        self.assert_owner();
+        require(payout_bps > 0 && payout_bps <= BPS_DENOMINATOR, RoulefiError::InvalidJackpotRules);
+        self.config.jackpot_chip = u128::from(jackpot_chip);
+        self.config.jackpot_payout_bps = payout_bps;
+        self.config.jackpot_streak = streak;
+        self.assert_solvent();
+    }
+
+    /*
+    fee paid to treasury by hosts other than owner to create a private table
+    */
+    pub fn set_table_creation_fee(&mut self, fee: U128) {
+        self.assert_owner();
+        self.config.table_creation_fee = u128::from(fee);
+        self.assert_solvent();
+    }
+
+    /*
+    no more bets within bet_close_delta blocks before a spin, it must leave every round of the main and private tables open for some blocks
+    */
+    pub fn set_bet_close_delta(&mut self, bet_close_delta: u64) {
+        self.assert_owner();
+        require(bet_close_delta < self.config.round_delta, RoulefiError::InvalidBetCloseDelta);
+        require(self.tables.iter().all(|table| bet_close_delta < table.round_delta), RoulefiError::InvalidBetCloseDelta);
+        self.config.bet_close_delta = bet_close_delta;
+        self.assert_solvent();
+    }
+
+    /*
+    blocks after a due spin before anyone can refund the round, at least MIN_REFUND_DELAY
+    */
+    pub fn set_refund_delay(&mut self, refund_delay: u64) {
+        self.assert_owner();
+        require(refund_delay >= MIN_REFUND_DELAY, RoulefiError::InvalidRefundDelay);
+        self.config.refund_delay = refund_delay;
+        self.assert_solvent();
+    }
+
+    /*
+    set how many recent spins the wheel stats keep
+    */
+    pub fn set_wheel_window(&mut self, window: u32) {
+        self.assert_owner();
+        require(window > 0 && window <= MAX_WHEEL_WINDOW, RoulefiError::InvalidStatsWindow);
+        self.config.wheel_window = window;
+        self.assert_solvent();
+    }
+}
@@ -0,0 +1,150 @@
+use crate::*;
+use near_sdk::json_types::ValidAccountId;
+
+/*
+what a referrer has brought in
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, Default)]
+pub struct ReferralStats {
+    pub referral_count: u64,        // players who registered the account as referrer
+    pub earned_amount: u128,        // commissions of all tiers
+}
+
+impl Contract {
+    /*
+    pay the referrers of a player a share of the house profit from the player's settled bets, tier by tier up the chain.
+    the referral pool pays first, the rest is returned to be taken from the round profit
+    */
+    pub(crate) fn internal_pay_referrals(&mut self, player_id: &AccountId, house_profit: u128) -> u128 {
+        if house_profit == 0 {
+            return 0;
+        }
+        let tiers = std::cmp::min(self.config.referral_tier_bps.len(), self.config.referral_max_tiers as usize);
+        let mut from_profit: u128 = 0;
+        let mut referee_id = player_id.clone();
+        for tier in 0..tiers {
+            let referrer_id = match self.referrers.get(&referee_id) {
+                Some(referrer_id) => referrer_id,
+                None => break,
+            };
+            if referrer_id == *player_id {
+                break;
+            }
+            let commission = safe_mul_div(house_profit, self.config.referral_tier_bps[tier] as u128, BPS_DENOMINATOR as u128);
+            if commission > 0 {
+                let from_pool = std::cmp::min(commission, self.treasury_status.referral_pool_amount);
+                self.treasury_status.referral_pool_amount = safe_sub(self.treasury_status.referral_pool_amount, from_pool);
+                from_profit = safe_add(from_profit, safe_sub(commission, from_pool));
+
+                let mut referrer = self.accounts.get(&referrer_id).unwrap_or(new_user());
+                referrer.balance = safe_add(referrer.balance, commission);
+                self.accounts.insert(&referrer_id, &referrer);
+                let mut stats = self.referral_stats.get(&referrer_id).unwrap_or_default();
+                stats.earned_amount = safe_add(stats.earned_amount, commission);
+                self.referral_stats.insert(&referrer_id, &stats);
+            }
+            referee_id = referrer_id;
+        }
+        from_profit
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+    register the caller's referrer, it can be done only once
+    */
+    pub fn register_referrer(&mut self, referrer_id: ValidAccountId) {
+        let sender_id = env::predecessor_account_id();
+        let referrer_id: AccountId = referrer_id.into();
+        require(self.referrers.get(&sender_id).is_none(), RoulefiError::AlreadyReferred);
+        require(referrer_id != sender_id && self.accounts.get(&referrer_id).is_some(), RoulefiError::InvalidReferrer);
+        let mut upper_id = referrer_id.clone();
+        for _ in 0..self.config.referral_max_tiers {      // no loop in the chain of paid tiers
+            match self.referrers.get(&upper_id) {
+                Some(id) => {
+                    require(id != sender_id, RoulefiError::InvalidReferrer);
+                    upper_id = id;
+                },
+                None => break,
+            }
+        }
+        self.referrers.insert(&sender_id, &referrer_id);
+        let mut stats = self.referral_stats.get(&referrer_id).unwrap_or_default();
+        stats.referral_count += 1;
+        self.referral_stats.insert(&referrer_id, &stats);
+        self.assert_solvent();
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+    use near_sdk::serde::export::TryFrom;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    fn register(contract: &mut Contract, account_id: &str, referrer_id: &str) {
+        let mut context = get_context();
+        context.predecessor_account_id = account_id.to_string();
+        testing_env!(context);
+        contract.deposit();
+        contract.register_referrer(ValidAccountId::try_from(referrer_id).unwrap());
+    }
+
+    #[test]
+    fn pay_referrals_by_tier() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.set_referral_policy(vec![1000, 500], 2);
+        contract.accounts.insert(&"carol".to_string(), &new_user());
+        register(&mut contract, "dave", "carol");
+        register(&mut contract, "erin", "dave");
+        contract.treasury_status.referral_pool_amount = 50;
+
+        let from_profit = contract.internal_pay_referrals(&"erin".to_string(), 1000);
+        assert_eq!(contract.accounts.get(&"dave".to_string()).unwrap().balance, 100);
+        assert_eq!(contract.accounts.get(&"carol".to_string()).unwrap().balance, 50);
+        assert_eq!(contract.treasury_status.referral_pool_amount, 0);
+        assert_eq!(from_profit, 100);
+        let stats = contract.referral_stats.get(&"carol".to_string()).unwrap();
+        assert_eq!(stats.referral_count, 1);
+        assert_eq!(stats.earned_amount, 50);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid referrer")]
+    fn referral_loop() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.accounts.insert(&"carol".to_string(), &new_user());
+        register(&mut contract, "dave", "carol");
+        register(&mut contract, "carol", "dave");
+    }
+}
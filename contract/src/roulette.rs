@@ -7,8 +7,11 @@ use near_sdk::{PanicOnDefault, Promise, env, log, near_bindgen};
 use near_sdk::{AccountId};
 use near_sdk::json_types::{U128, U64};
 use crate::view::BetInfo;
+use crate::limits::LimitKind;
 use crate::*;
 
+pub const MIN_REFUND_DELAY: u64 = 100;     // about a minute for keepers to spin a due round before bets can be refunded
+
 
 /*
 check if the bet wins
@@ -53,6 +56,65 @@ pub fn check_win(number:u8, b: &Bet) -> bool {
     won
 }
 
+/*
+winning number from the random seed with the last bet as nonce, salt tells the tables apart
+*/
+pub(crate) fn wheel_number(last_player_id: &AccountId, bet: &Bet, salt: Vec<u8>) -> u8 {
+    let nonce: Vec<u8> = vec![bet.number, bet.bet_type];
+    let hash = env::sha256(&[env::random_seed(), nonce, last_player_id[..].as_bytes().to_vec(), salt].concat());  //make hash with nonce
+    let mut hash_bytes: [u8;4] = [0;4];
+    for i in 0..4 {
+        hash_bytes[i] = hash.get(i).unwrap().clone();
+    }
+    let hash_number = u32::from_be_bytes(hash_bytes);   // generate random number
+    hash_number as u8 % 37
+}
+
+/*
+check if the bet item is valid and fits the table limits
+*/
+pub fn validate_bet(limits: &TableLimits, b: &Bet) -> Result<(), RoulefiError> {
+    ensure(b.bet_type <= 5, RoulefiError::InvalidBetType)?;
+    ensure(b.number <= NUMBER_RANGE[b.bet_type as usize], RoulefiError::InvalidBetNumber)?;
+    ensure(b.chips > 0 && b.chips % limits.chip_unit == 0, RoulefiError::InvalidChips)?;
+    ensure(b.chips >= limits.min_chips[b.bet_type as usize], RoulefiError::LessThanMinBet)?;
+    ensure(b.chips <= limits.max_chips[b.bet_type as usize], RoulefiError::ExceedMaxBet)
+}
+
+pub fn check_bet_limits(limits: &TableLimits, b: &Bet) {
+    validate_bet(limits, b).or_fail();
+}
+
+pub fn to_bets(bets: &Vec<BetInfo>) -> Vec<Bet> {
+    bets.iter().map(|bet| {
+        Bet {
+            chips: u128::from(bet.chips),
+            bet_type: bet.bet_type,
+            number: bet.number
+        }
+    }).collect()
+}
+
+/*
+check every bet item, then the chips on the same bet type and number together with the bets already placed in the round,
+so repeating a bet can't pass the max chips. returns the chips of the new bets
+*/
+pub fn validate_bets(limits: &TableLimits, placed: &Vec<Bet>, bets: &Vec<Bet>) -> Result<u128, RoulefiError> {
+    ensure(bets.len() > 0, RoulefiError::EmptyBets)?;
+    let mut total: u128 = 0;
+    for item in bets.iter() {
+        validate_bet(limits, item)?;
+        total = try_add(total, item.chips)?;
+    }
+    for item in bets.iter() {
+        let chips = placed.iter().chain(bets.iter())
+            .filter(|bet| bet.bet_type == item.bet_type && bet.number == item.number)
+            .try_fold(0, |chips, bet| try_add(chips, bet.chips))?;
+        ensure(chips <= limits.max_chips[item.bet_type as usize], RoulefiError::ExceedMaxBet)?;
+    }
+    Ok(total)
+}
+
 
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -71,6 +133,9 @@ pub struct Stake {
     pub time: u64,
     pub profit: u128,
     pub loss: u128,
+    pub reward_index: u128,   // stake_reward_index when the treasury reward was last settled
+    pub reward_weight: u128,  // weight of the stake when the treasury reward was last settled
+    pub reward: u128,         // treasury reward settled but not claimed yet
 }
 
 
@@ -78,90 +143,189 @@ pub struct Stake {
 #[near_bindgen]
 impl Contract {
 
+    /*
+    place the bets of current round, with_jackpot adds a jackpot side bet of jackpot_chip
+    */
     #[payable]
-    pub fn bet(&mut self, bets: Vec<BetInfo>, round_index: U64) {
-        assert!(!self.round_status.spinning, "wheel spinning, try later");
-        assert!(self.round_status.round_index == u64::from(round_index), "uncorrect round index");
+    pub fn bet(&mut self, bets: Vec<BetInfo>, round_index: U64, with_jackpot: Option<bool>) {
+        require(!self.round_status.spinning, RoulefiError::WheelSpinning);
+        require(self.round_status.round_index == u64::from(round_index), RoulefiError::WrongRoundIndex);
         //let prev_storage = env::storage_usage();
+        self.internal_open_round();
         let sender_id = env::predecessor_account_id();
+        self.assert_not_excluded(&sender_id);
         let mut account = self.accounts.get(&sender_id).unwrap_or(new_user());
-        assert!(bets.len() > 0, "you have 0 bets");
-        assert!(account.bets.len() == 0, "you've already bet");
-        let mut total:u128 = 0;
-        for item in bets.iter() {
-            total += u128::from(item.chips);
-            assert!(item.bet_type <= 5);
-            assert!(item.number <= NUMBER_RANGE[item.bet_type as usize]); 
+        require(account.bets.len() == 0, RoulefiError::AlreadyBet);
+        account.balance = safe_add(account.balance, env::attached_deposit());   // user's deposit amount and current trasaction's deposit are used for the bets
+        self.internal_use_limit(&sender_id, LimitKind::Deposit, env::attached_deposit());
+        self.internal_place_bets(&mut account, bets);
+        if with_jackpot.unwrap_or(false) {
+            self.internal_enter_jackpot(&mut account);
         }
-        let balance = account.balance + env::attached_deposit();   // check if user's deposit amount and current trasaction's deposit are greater than the bets
-        assert!(balance >= total, "not enough balance"); 
-        self.round_status.bet_amount += total;
-        assert!(self.round_status.bet_amount < self.round_status.max_amount_allowed, "exceed max bet amount allowed");  // check if total bet amount is greater than the max amount allowed
-        account.balance = balance - total;  // the balance decrease when bet is confirmed
-        account.bets = bets.iter().map(|bet| {
-            Bet {
-                chips: u128::from(bet.chips),
-                bet_type: bet.bet_type,
-                number: bet.number
-            }
-        }).collect();
+        self.internal_use_limit(&sender_id, LimitKind::Wager, self.round_bet_chips(&account) + account.jackpot_chips);
+        self.assert_loss_limit(&sender_id, &account);
+        self.assert_gas_reserved(&account);
         self.accounts.insert(&sender_id, &account);
         self.bet_accounts.push(&sender_id);
+        self.assert_solvent();
+    }
+
+    /*
+    add more bets to current round
+    */
+    #[payable]
+    pub fn add_bets(&mut self, bets: Vec<BetInfo>, round_index: U64) {
+        require(!self.round_status.spinning, RoulefiError::WheelSpinning);
+        require(self.round_status.round_index == u64::from(round_index), RoulefiError::WrongRoundIndex);
+        self.assert_bet_open();
+        let sender_id = env::predecessor_account_id();
+        let mut account = self.accounts.get(&sender_id).or_fail(RoulefiError::AccountNotFound);
+        require(account.bets.len() > 0, RoulefiError::NoBetsInRound);
+        self.assert_not_excluded(&sender_id);
+        account.balance = safe_add(account.balance, env::attached_deposit());
+        self.internal_use_limit(&sender_id, LimitKind::Deposit, env::attached_deposit());
+        let prev_chips = self.round_bet_chips(&account);
+        self.internal_place_bets(&mut account, bets);
+        self.internal_use_limit(&sender_id, LimitKind::Wager, self.round_bet_chips(&account) - prev_chips);
+        self.assert_loss_limit(&sender_id, &account);
+        self.assert_gas_reserved(&account);
+        self.accounts.insert(&sender_id, &account);
+        self.assert_solvent();
+    }
+
+    /*
+    refund the bets of current round and bet again
+    */
+    #[payable]
+    pub fn replace_bets(&mut self, bets: Vec<BetInfo>, round_index: U64) {
+        require(!self.round_status.spinning, RoulefiError::WheelSpinning);
+        require(self.round_status.round_index == u64::from(round_index), RoulefiError::WrongRoundIndex);
+        self.assert_bet_open();
+        let sender_id = env::predecessor_account_id();
+        let mut account = self.accounts.get(&sender_id).or_fail(RoulefiError::AccountNotFound);
+        require(account.bets.len() > 0, RoulefiError::NoBetsInRound);
+        self.assert_not_excluded(&sender_id);
+        account.balance = safe_add(account.balance, env::attached_deposit());
+        self.internal_use_limit(&sender_id, LimitKind::Deposit, env::attached_deposit());
+        let prev_chips = self.round_bet_chips(&account);
+        self.internal_refund_bets(&sender_id, &mut account);
+        self.internal_place_bets(&mut account, bets);
+        self.internal_use_limit(&sender_id, LimitKind::Wager, self.round_bet_chips(&account).saturating_sub(prev_chips));   // only more chips than the replaced bets count
+        self.assert_loss_limit(&sender_id, &account);
+        self.assert_gas_reserved(&account);
+        self.accounts.insert(&sender_id, &account);
+        self.assert_solvent();
+    }
+
+    /*
+    refund the bets of current round to balance
+    */
+    pub fn cancel_bet(&mut self, round_index: U64) {
+        require(!self.round_status.spinning, RoulefiError::WheelSpinning);
+        require(self.round_status.round_index == u64::from(round_index), RoulefiError::WrongRoundIndex);
+        self.assert_bet_open();
+        let sender_id = env::predecessor_account_id();
+        let mut account = self.accounts.get(&sender_id).or_fail(RoulefiError::AccountNotFound);
+        require(account.bets.len() > 0, RoulefiError::NoBetsInRound);
+        self.internal_refund_bets(&sender_id, &mut account);
+        self.internal_refund_jackpot(&mut account);
+        self.accounts.insert(&sender_id, &account);
+        self.remove_bet_account(&sender_id);
+        self.assert_solvent();
     }
 
 
     /*
-    this method is controlled by a script. the script keeps getting round status until there is any bet
-    and time to next_round_block_index, then call this method
+    anyone can spin the wheel after next_round_block_index if there is any bet,
+    the caller gets gas_per_player from every player of the round as keeper reward
     */
     pub fn spin_wheel(&mut self, round_index: U64) {
-        assert!(self.round_status.round_index == u64::from(round_index), "uncorrect round index");
-        assert!(env::block_index() > self.round_status.current_round_block_index + self.config.round_delta, "too quick to spin");
-        assert!(self.bet_accounts.len() > 0, "no bets");
+        require(self.round_status.round_index == u64::from(round_index), RoulefiError::WrongRoundIndex);
+        require(env::block_index() > self.round_status.next_round_block_index, RoulefiError::TooQuickToSpin);
+        require(self.bet_accounts.len() > 0, RoulefiError::RoundWithoutBets);
         self.round_status.spinning = true;
 
         let last_player_id = self.bet_accounts.get(self.bet_accounts.len() - 1).unwrap();
         let bets = self.accounts.get(&last_player_id).unwrap().bets;
         let bet = bets.get(bets.len() - 1).unwrap();
-        let nonce: Vec<u8> = vec![bet.number, bet.bet_type];
-        let hash = env::sha256(&[env::random_seed(), nonce, last_player_id[..].as_bytes().to_vec()].concat());  //make hash with nonce
-        let mut hash_bytes: [u8;4] = [0;4];
-        for i in 0..4 {
-            hash_bytes[i] = hash.get(i).unwrap().clone();
-        }
-        let hash_number = u32::from_be_bytes(hash_bytes);   // generate random number
-        let number: u8 = hash_number as u8 % 37 ;
+        let number = wheel_number(&last_player_id, bet, Vec::new());
         
         let mut total_bet:u128 = 0;
         let mut total_win:u128 = 0;
-        for player_id in self.bet_accounts.iter() {                 // check every bet if it wins
+        let mut keeper_reward:u128 = 0;
+        let mut total_commission:u128 = 0;
+        let mut jackpot_winners: Vec<AccountId> = Vec::new();
+        let mut compound_rewards: Vec<(AccountId, u128)> = Vec::new();
+        let mut results: Vec<(AccountId, u128, u128)> = Vec::new();
+        for player_id in self.bet_accounts.to_vec() {                 // check every bet if it wins
             let mut account = self.accounts.get(&player_id).unwrap();
             let mut bet_amount = 0;
             let mut win_amount = 0;
             for b in account.bets.iter() {
                 let won = check_win(number, b);
-                bet_amount += u128::from(b.chips);
+                bet_amount = safe_add(bet_amount, u128::from(b.chips));
                 let mut win_chips = 0;
                 if won {
-                    win_chips = PAYOUTS[b.bet_type as usize] as u128 * u128::from(b.chips);
-                    win_amount += win_chips.clone();
+                    win_chips = safe_mul(PAYOUTS[b.bet_type as usize] as u128, u128::from(b.chips));
+                    win_amount = safe_add(win_amount, win_chips.clone());
                     
                 }      
             }
-            account.balance += win_amount;
-            total_bet += bet_amount;
-            total_win += win_amount;
+            account.balance = safe_add(account.balance, win_amount);
+            let fee = std::cmp::min(self.config.gas_per_player, account.balance);   // keeper fee
+            account.balance -= fee;
+            keeper_reward = safe_add(keeper_reward, fee);
+            total_bet = safe_add(total_bet, bet_amount);
+            total_win = safe_add(total_win, win_amount);
+            if self.internal_settle_jackpot(&mut account, number) {
+                jackpot_winners.push(player_id.clone());
+            }
             account.bets.clear();
+            let reward = self.internal_record_player(&player_id, &mut account, bet_amount);
+            if account.auto_compound && reward > 0 {
+                compound_rewards.push((player_id.clone(), reward));
+            }
+            self.internal_record_loss(&player_id, bet_amount, win_amount);
+            self.internal_settle_strategy(&player_id, bet_amount, win_amount);
+            results.push((player_id.clone(), bet_amount, win_amount));
             self.accounts.insert(&player_id, &account);
+            let commission = self.internal_pay_referrals(&player_id, bet_amount.saturating_sub(win_amount));
+            total_commission = safe_add(total_commission, commission);
         }
-        self.cal_profit(total_bet, total_win);
+        self.cal_profit(total_bet, safe_add(total_win, total_commission));   // commissions not covered by the referral pool are paid by the house
+        self.internal_pay_jackpot(jackpot_winners, number);
+        self.internal_compound_rewards(compound_rewards);
+        self.internal_record_stats(&results);
         
-        self.bet_accounts.clear();
-        self.round_status.bet_amount = 0;
-        self.round_status.current_round_block_index = env::block_index();
-        self.round_status.round_index += 1;
+        self.internal_next_round();
         self.round_status.last_round_win_number = number;
+        self.wheel_stats.record(number, self.config.wheel_window);
         self.round_status.spinning = false;
+        self.internal_place_standing_orders();
+        if keeper_reward > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(keeper_reward);
+        }
+        self.assert_solvent();
+    }
+
+    /*
+    if nobody spins the wheel in refund_delay blocks after next_round_block_index,
+    anyone can give all bets of the round back to players' balance and move to next round
+    */
+    pub fn refund_round(&mut self, round_index: U64) {
+        require(self.round_status.round_index == u64::from(round_index), RoulefiError::WrongRoundIndex);
+        require(!self.round_status.spinning, RoulefiError::WheelSpinning);
+        require(self.bet_accounts.len() > 0, RoulefiError::RoundWithoutBets);
+        require(env::block_index() > self.round_status.next_round_block_index + self.config.refund_delay, RoulefiError::TooQuickToRefund);
+        for player_id in self.bet_accounts.to_vec() {
+            let mut account = self.accounts.get(&player_id).unwrap();
+            self.internal_refund_bets(&player_id, &mut account);
+            self.internal_refund_jackpot(&mut account);
+            self.accounts.insert(&player_id, &account);
+        }
+        self.internal_next_round();
+        self.internal_place_standing_orders();
+        self.assert_solvent();
     }
 
     /*
@@ -170,9 +334,12 @@ impl Contract {
     #[payable]
     pub fn deposit(&mut self) {                   
         let sender_id = env::predecessor_account_id();
+        self.assert_not_excluded(&sender_id);
+        self.internal_use_limit(&sender_id, LimitKind::Deposit, env::attached_deposit());
         let mut account = self.accounts.get(&sender_id).unwrap_or(new_user());
-        account.balance += env::attached_deposit();
+        account.balance = safe_add(account.balance, env::attached_deposit());
         self.accounts.insert(&sender_id, &account);
+        self.assert_solvent();
     }
 
     /*
@@ -180,14 +347,18 @@ impl Contract {
     */
     pub fn withdraw(&mut self, amount: U128) {                  
         let sender_id = env::predecessor_account_id();
-        let mut account = self.accounts.get(&sender_id).expect("account not found");
+        let mut account = self.accounts.get(&sender_id).or_fail(RoulefiError::AccountNotFound);
         let amount = u128::from(amount);
-        assert!(amount <= account.balance, "not enough balance");
-        assert!(amount > 0, "not enough amount!");
-        assert!(amount <= env::account_balance(), "not enough balance!");
-        account.balance -= amount;
+        require(amount <= account.balance, RoulefiError::NotEnoughBalance);
+        require(amount > 0, RoulefiError::NotEnoughAmount);
+        if account.bets.len() > 0 {
+            require(account.balance - amount >= self.config.gas_per_player, RoulefiError::GasNotReserved);
+        }
+        require(amount <= env::account_balance(), RoulefiError::NotEnoughContractBalance);
+        account.balance = safe_sub(account.balance, amount);
         self.accounts.insert(&sender_id.clone(), &account);
         Promise::new(sender_id).transfer(amount.clone());
+        self.assert_solvent();
     }
 
 }
@@ -217,8 +388,8 @@ mod tests {
             predecessor_account_id: "bob".to_string(),
             input: vec![],
             block_index: 0,
-            block_timestamp: env::block_timestamp() - 100,
-            account_balance: 100000000000000000,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
             account_locked_balance: 0,
             storage_usage: 10u64.pow(6),
             attached_deposit: 10000000000000000,
@@ -251,4 +422,254 @@ mod tests {
         let number = 0;//contract.spin_wheel(bets);
         println!("{}", number);
     }
+
+    fn stake_pool(contract: &mut Contract) {
+        let mut context = get_context();
+        context.predecessor_account_id = "alice".to_string();
+        context.attached_deposit = CHIP_UNIT * 100000;   // 1000 NEAR
+        testing_env!(context);
+        contract.stake();
+    }
+
+    fn red(chips: u128) -> Vec<BetInfo> {
+        vec![BetInfo {
+            bet_type: 0,
+            number: 1,
+            chips: U128::from(chips)
+        }]
+    }
+
+    #[test]
+    fn cancel_bet_refunds_balance() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.bet(red(CHIP_UNIT * 10), U64::from(0), None);
+        assert_eq!(contract.bet_accounts.len(), 1);
+        assert_eq!(contract.accounts.get(&"bob".to_string()).unwrap().balance, CHIP_UNIT * 90);
+
+        contract.cancel_bet(U64::from(0));
+        let account = contract.accounts.get(&"bob".to_string()).unwrap();
+        assert_eq!(account.balance, CHIP_UNIT * 100);
+        assert!(account.bets.is_empty());
+        assert_eq!(contract.bet_accounts.len(), 0);
+        assert_eq!(contract.round_status.bet_amount, 0);
+    }
+
+    #[test]
+    fn replace_bets_swaps_wager() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.bet(red(CHIP_UNIT * 10), U64::from(0), None);
+
+        let mut context = get_context();
+        context.attached_deposit = 0;
+        testing_env!(context);
+        contract.replace_bets(red(CHIP_UNIT * 30), U64::from(0));
+        let account = contract.accounts.get(&"bob".to_string()).unwrap();
+        assert_eq!(account.balance, CHIP_UNIT * 70);
+        assert_eq!(account.bets.len(), 1);
+        assert_eq!(contract.round_status.bet_amount, CHIP_UNIT * 30);
+        assert_eq!(contract.bet_accounts.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "betting closed for this round")]
+    fn cancel_bet_after_close() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.bet(red(CHIP_UNIT * 10), U64::from(0), None);
+
+        let mut context = get_context();
+        context.block_index = contract.round_status.next_round_block_index - contract.config.bet_close_delta + 1;
+        testing_env!(context);
+        contract.cancel_bet(U64::from(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "betting closed for this round")]
+    fn bet_after_close() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.bet(red(CHIP_UNIT * 10), U64::from(0), None);
+
+        let mut context = get_context();
+        context.predecessor_account_id = "carol".to_string();
+        context.attached_deposit = CHIP_UNIT * 100;
+        context.block_index = contract.bet_close_block_index() + 1;
+        testing_env!(context);
+        assert_eq!(contract.round_state(), RoundState::Closed);
+        contract.bet(red(CHIP_UNIT * 10), U64::from(0), None);
+    }
+
+    #[test]
+    fn bet_restarts_idle_round() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        context.block_index = contract.round_status.next_round_block_index + 100;
+        testing_env!(context);
+        assert_eq!(contract.round_state(), RoundState::Spinning);
+        contract.bet(red(CHIP_UNIT * 10), U64::from(0), None);
+        assert_eq!(contract.round_state(), RoundState::Open);
+        assert_eq!(contract.round_status.next_round_block_index, env::block_index() + contract.config.round_delta);
+    }
+
+    #[test]
+    fn spin_wheel_charges_keeper_fee() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.bet(red(CHIP_UNIT * 10), U64::from(0), None);
+
+        let mut context = get_context();
+        context.predecessor_account_id = "carol".to_string();
+        context.block_index = contract.round_status.next_round_block_index + 1;
+        testing_env!(context);
+        contract.spin_wheel(U64::from(0));
+        let balance = contract.accounts.get(&"bob".to_string()).unwrap().balance;
+        let fee = contract.config.gas_per_player;
+        assert!(balance == CHIP_UNIT * 90 - fee || balance == CHIP_UNIT * 110 - fee);
+        assert_eq!(contract.round_status.round_index, 1);
+    }
+
+    #[test]
+    fn refund_round_after_delay() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.bet(red(CHIP_UNIT * 10), U64::from(0), None);
+
+        let mut context = get_context();
+        context.predecessor_account_id = "carol".to_string();
+        context.block_index = contract.round_status.next_round_block_index + contract.config.refund_delay + 1;
+        testing_env!(context);
+        contract.refund_round(U64::from(0));
+        let account = contract.accounts.get(&"bob".to_string()).unwrap();
+        assert_eq!(account.balance, CHIP_UNIT * 100);
+        assert!(account.bets.is_empty());
+        assert_eq!(contract.bet_accounts.len(), 0);
+        assert_eq!(contract.round_status.bet_amount, 0);
+        assert_eq!(contract.round_status.round_index, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "too quick to refund")]
+    fn refund_round_too_early() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.bet(red(CHIP_UNIT * 10), U64::from(0), None);
+
+        let mut context = get_context();
+        context.block_index = contract.round_status.next_round_block_index + 1;
+        testing_env!(context);
+        contract.refund_round(U64::from(0));
+    }
+
+    #[test]
+    fn check_bet_limits_accepts_chip_multiples() {
+        let context = get_context();
+        testing_env!(context);
+        let contract = Contract::new();
+        check_bet_limits(&contract.config.table_limits, &Bet {
+            bet_type: 0,
+            number: 1,
+            chips: CHIP_UNIT * 3
+        });
+        check_bet_limits(&contract.config.table_limits, &Bet {
+            bet_type: 5,
+            number: 36,
+            chips: CHIP_UNIT * 1000
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "chips must be a multiple of chip unit")]
+    fn check_bet_limits_rejects_dust() {
+        let context = get_context();
+        testing_env!(context);
+        let contract = Contract::new();
+        check_bet_limits(&contract.config.table_limits, &Bet {
+            bet_type: 0,
+            number: 1,
+            chips: CHIP_UNIT + 1
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "exceed max bet")]
+    fn repeated_bets_share_max() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 2000;
+        testing_env!(context);
+        let straight = |chips: u128| BetInfo {
+            bet_type: 5,
+            number: 7,
+            chips: U128::from(chips)
+        };
+        contract.bet(vec![straight(CHIP_UNIT * 600)], U64::from(0), None);
+        assert!(validate_bets(&contract.config.table_limits, &Vec::new(), &vec![Bet { bet_type: 5, number: 7, chips: CHIP_UNIT * 600 }; 2]).is_err());
+        contract.add_bets(vec![straight(CHIP_UNIT * 600)], U64::from(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceed max bet")]
+    fn check_bet_limits_rejects_above_max() {
+        let context = get_context();
+        testing_env!(context);
+        let contract = Contract::new();
+        check_bet_limits(&contract.config.table_limits, &Bet {
+            bet_type: 5,
+            number: 7,
+            chips: CHIP_UNIT * 1001
+        });
+    }
 }
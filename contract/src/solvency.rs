@@ -0,0 +1,137 @@
+use crate::*;
+
+/*
+what the contract owes against what it holds, liabilities must never exceed assets
+*/
+#[derive(Debug, Clone, Default)]
+pub struct Reserves {
+    pub balance_amount: u128,       // players' balance
+    pub bet_amount: u128,           // chips of open bets and jackpot side bets
+    pub pool_amount: u128,          // stake values
+    pub treasury_amount: u128,      // treasury, unclaimed rewards, referral pool and vesting
+    pub insurance_amount: u128,
+    pub jackpot_amount: u128,       // jackpot pot
+    pub tournament_amount: u128,    // prize pools of tournaments not finished
+    pub table_amount: u128,         // separate bankrolls and open bets of private tables
+    pub storage_amount: u128,       // locked for contract storage
+    pub contract_balance: u128,
+    pub delegated_amount: u128,     // staked, unstaked and in flight to the validator pool
+}
+
+impl Reserves {
+    pub fn liabilities(&self) -> u128 {
+        [self.bet_amount, self.pool_amount, self.treasury_amount, self.insurance_amount, self.jackpot_amount, self.tournament_amount, self.table_amount, self.storage_amount]
+            .iter()
+            .fold(self.balance_amount, |total, amount| safe_add(total, *amount))
+    }
+
+    pub fn assets(&self) -> u128 {
+        safe_add(self.contract_balance, self.delegated_amount)
+    }
+}
+
+impl Contract {
+    /*
+    walk every account to sum the liabilities, the pool is taken from the aggregates which never fall
+    below the sum of stake values
+    */
+    pub(crate) fn cal_reserves(&self) -> Reserves {
+        let mut reserves = Reserves::default();
+        for account in self.accounts.values() {
+            reserves.balance_amount = safe_add(reserves.balance_amount, account.balance);
+            reserves.bet_amount = account.bets.iter().fold(reserves.bet_amount, |total, bet| safe_add(total, bet.chips));
+            reserves.bet_amount = safe_add(reserves.bet_amount, account.jackpot_chips);
+        }
+        reserves.pool_amount = self.pool_amount();
+        reserves.treasury_amount = safe_add(
+            safe_add(self.treasury_status.treasury_amount, self.treasury_status.unclaimed_amount),
+            safe_add(self.treasury_status.referral_pool_amount, self.treasury_status.vesting_amount)
+        );
+        reserves.insurance_amount = self.treasury_status.insurance_amount;
+        reserves.jackpot_amount = self.jackpot.pot_amount;
+        for tournament in self.tournaments.iter() {
+            if !tournament.finished {
+                reserves.tournament_amount = safe_add(reserves.tournament_amount, tournament.prize_pool);
+            }
+        }
+        for table in self.tables.iter() {
+            if table.tournament_id.is_none() {      // tournament chips are not near
+                reserves.table_amount = safe_add(reserves.table_amount, safe_add(table.bankroll_amount, table.bet_amount));
+            }
+        }
+        reserves.storage_amount = env::storage_usage() as u128 * env::storage_byte_cost();
+        reserves.contract_balance = env::account_balance();
+        reserves.delegated_amount = safe_add(safe_add(self.delegation.staked_amount, self.delegation.unstaked_amount), self.delegation.pending_amount);
+        reserves
+    }
+
+    /*
+    checked at the end of every method changing the state in unit tests and in builds with the solvency-check feature.
+    it walks all accounts, tournaments and tables, so release builds skip it
+    */
+    pub(crate) fn assert_solvent(&self) {
+        if cfg!(any(test, feature = "solvency-check")) {
+            let reserves = self.cal_reserves();
+            if reserves.assets() < reserves.liabilities() {
+                env::panic(format!("{}: liabilities {} exceed assets {}", RoulefiError::Insolvent, reserves.liabilities(), reserves.assets()).as_bytes());
+            }
+        }
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    #[test]
+    fn reserves_cover_deposits_and_stakes() {
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.deposit();
+        contract.stake();
+        contract.treasury_status.treasury_amount = 300;
+
+        let reserves = contract.cal_reserves();
+        assert_eq!(reserves.balance_amount, CHIP_UNIT * 100);
+        assert_eq!(reserves.pool_amount, CHIP_UNIT * 100);
+        assert_eq!(reserves.treasury_amount, 300);
+        assert!(reserves.assets() >= reserves.liabilities());
+    }
+
+    #[test]
+    #[should_panic(expected = "insolvent")]
+    fn treasury_not_covered() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.treasury_status.treasury_amount = CHIP_UNIT * 200000000;
+        contract.assert_solvent();
+    }
+}
@@ -0,0 +1,258 @@
+use crate::*;
+use crate::view::BetInfo;
+use crate::limits::LimitKind;
+
+const MAX_STANDING_ORDERS: u64 = 50;     // the next spin_wheel settles these players and then places every order again, on top of the manual bettors
+
+/*
+a bet layout placed again from balance every round
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone)]
+pub struct StandingOrder {
+    pub bets: Vec<Bet>,
+    pub rounds_left: Option<u64>,       // None until cancelled
+    pub placed_rounds: u64,
+    pub skipped_rounds: u64,
+    pub last_error: Option<u32>,        // code of the error that skipped the last round
+}
+
+impl Contract {
+    /*
+    place the standing order for current round, every check is done before anything changes
+    */
+    fn try_place_standing_order(&mut self, account_id: &AccountId, account: &mut Account, order: &StandingOrder) -> Result<(), RoulefiError> {
+        ensure(account.bets.len() == 0, RoulefiError::AlreadyBet)?;
+        self.check_not_excluded(account_id)?;
+        let bets = self.strategy_bets(account_id, &order.bets);
+        let total = validate_bets(&self.config.table_limits, &account.bets, &bets)?;     // a progression may grow past the table limits
+        ensure(account.balance >= try_add(total, self.config.gas_per_player)?, RoulefiError::GasNotReserved)?;
+        self.check_limit(account_id, LimitKind::Wager, total)?;
+        self.check_limit(account_id, LimitKind::Loss, total)?;
+        self.try_place_bets(account, bets)?;
+        self.internal_use_limit(account_id, LimitKind::Wager, total);
+        Ok(())
+    }
+
+    /*
+    place the standing order of one account in current round, a round that doesn't allow it is skipped
+    */
+    pub(crate) fn internal_place_standing_order(&mut self, account_id: &AccountId) {
+        let mut order = match self.standing_orders.get(account_id) {
+            Some(order) => order,
+            None => return,
+        };
+        let mut account = self.accounts.get(account_id).unwrap_or(new_user());
+        match self.try_place_standing_order(account_id, &mut account, &order) {
+            Ok(()) => {
+                self.accounts.insert(account_id, &account);
+                self.bet_accounts.push(account_id);
+                order.placed_rounds += 1;
+                self.internal_mark_strategy_placed(account_id);
+                order.last_error = None;
+            },
+            Err(error) => {
+                order.skipped_rounds += 1;
+                order.last_error = Some(error.code());
+            },
+        }
+        if let Some(rounds_left) = order.rounds_left {
+            order.rounds_left = Some(rounds_left - 1);
+        }
+        if order.rounds_left == Some(0) {
+            self.standing_orders.remove(account_id);
+            self.standing_accounts.remove(account_id);
+        } else {
+            self.standing_orders.insert(account_id, &order);
+        }
+    }
+
+    /*
+    called when a new round starts
+    */
+    pub(crate) fn internal_place_standing_orders(&mut self) {
+        for account_id in self.standing_accounts.to_vec() {
+            self.internal_place_standing_order(&account_id);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+    repeat the bets for the next rounds from balance, rounds is 0 to repeat until cancelled.
+    the bets are placed in current round at once if betting is open
+    */
+    pub fn place_standing_order(&mut self, bets: Vec<BetInfo>, rounds: U64) {
+        let sender_id = env::predecessor_account_id();
+        self.assert_not_excluded(&sender_id);
+        require(self.standing_orders.get(&sender_id).is_some() || self.standing_accounts.len() < MAX_STANDING_ORDERS, RoulefiError::StandingOrdersFull);
+        let bets = to_bets(&bets);
+        validate_bets(&self.config.table_limits, &Vec::new(), &bets).or_fail();
+        let rounds = u64::from(rounds);
+        self.standing_orders.insert(&sender_id, &StandingOrder {
+            bets,
+            rounds_left: if rounds == 0 { None } else { Some(rounds) },
+            placed_rounds: 0,
+            skipped_rounds: 0,
+            last_error: None,
+        });
+        self.standing_accounts.insert(&sender_id);
+        if !self.round_status.spinning && self.round_state() == RoundState::Open {
+            let account = self.accounts.get(&sender_id).unwrap_or(new_user());
+            if account.bets.len() == 0 {
+                self.internal_place_standing_order(&sender_id);
+            }
+        }
+        self.assert_solvent();
+    }
+
+    /*
+    stop the standing order, bets already placed in current round stay
+    */
+    pub fn cancel_standing_order(&mut self) {
+        let sender_id = env::predecessor_account_id();
+        require(self.standing_orders.remove(&sender_id).is_some(), RoulefiError::StandingOrderNotFound);
+        self.standing_accounts.remove(&sender_id);
+        self.strategies.remove(&sender_id);
+        self.assert_solvent();
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::Progression;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    fn stake_pool(contract: &mut Contract) {
+        let mut context = get_context();
+        context.predecessor_account_id = "alice".to_string();
+        context.attached_deposit = CHIP_UNIT * 100000;   // 1000 NEAR
+        testing_env!(context);
+        contract.stake();
+    }
+
+    fn red(chips: u128) -> Vec<BetInfo> {
+        vec![BetInfo {
+            bet_type: 0,
+            number: 1,
+            chips: U128::from(chips)
+        }]
+    }
+
+    #[test]
+    fn standing_order_repeats_each_round() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.deposit();
+        contract.place_standing_order(red(CHIP_UNIT * 10), U64::from(2));
+        assert_eq!(contract.bet_accounts.len(), 1);
+        assert_eq!(contract.standing_orders.get(&"bob".to_string()).unwrap().rounds_left, Some(1));
+
+        let mut context = get_context();
+        context.block_index = 61;
+        testing_env!(context);
+        contract.spin_wheel(U64::from(0));
+        let account = contract.accounts.get(&"bob".to_string()).unwrap();
+        assert_eq!(account.bets.len(), 1);
+        assert_eq!(contract.bet_accounts.len(), 1);
+        assert!(contract.standing_orders.get(&"bob".to_string()).is_none());
+        assert_eq!(contract.standing_accounts.len(), 0);
+    }
+
+    #[test]
+    fn standing_order_skips_without_balance() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let context = get_context();
+        testing_env!(context);
+        contract.place_standing_order(red(CHIP_UNIT * 10), U64::from(0));
+        assert_eq!(contract.bet_accounts.len(), 0);
+        let order = contract.standing_orders.get(&"bob".to_string()).unwrap();
+        assert_eq!(order.skipped_rounds, 1);
+        assert_eq!(order.last_error, Some(RoulefiError::GasNotReserved.code()));
+        assert_eq!(order.rounds_left, None);
+    }
+
+    #[test]
+    fn standing_order_skips_on_overflow() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+        contract.config.table_limits.max_chips[0] = u128::MAX;
+
+        let context = get_context();
+        testing_env!(context);
+        contract.place_standing_order(red(u128::MAX - u128::MAX % CHIP_UNIT), U64::from(0));     // chips and gas fee overflow
+        let order = contract.standing_orders.get(&"bob".to_string()).unwrap();
+        assert_eq!(order.skipped_rounds, 1);
+        assert_eq!(order.last_error, Some(RoulefiError::Overflow.code()));
+    }
+
+    #[test]
+    fn refunded_round_not_settled_by_strategy() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.deposit();
+        contract.place_standing_order(red(CHIP_UNIT * 10), U64::from(0));
+        contract.set_strategy(Progression::Martingale, U128::from(CHIP_UNIT * 10), U128::from(0), U128::from(0), 5);
+
+        let mut context = get_context();
+        context.block_index = 61;
+        testing_env!(context);
+        contract.spin_wheel(U64::from(0));
+        assert!(contract.strategies.get(&"bob".to_string()).unwrap().placed);
+        contract.cancel_bet(U64::from(1));
+        assert!(!contract.strategies.get(&"bob".to_string()).unwrap().placed);
+        contract.bet(red(CHIP_UNIT * 20), U64::from(1), None);      // a manual bet is not the strategy's
+
+        let mut context = get_context();
+        context.block_index = 122;
+        testing_env!(context);
+        contract.spin_wheel(U64::from(1));
+        let strategy = contract.strategies.get(&"bob".to_string()).unwrap();
+        assert_eq!(strategy.bet_amount, 0);
+        assert_eq!(strategy.step, 0);
+    }
+}
@@ -0,0 +1,298 @@
+use crate::*;
+
+pub const STATS_DAYS: u64 = 30;            // longest rolling window of the leaderboards
+pub const MAX_LEADERBOARD_SIZE: u32 = 100;
+pub const ALL_TIME: u64 = u64::MAX;        // leaderboard key of all time, the others are keyed by day
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, Default)]
+pub struct DayStats {
+    pub day: u64,               // days since unix epoch
+    pub rounds: u64,
+    pub wagered: u128,
+    pub won: u128,
+}
+
+/*
+what a player has done at the table, updated at settlement
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, Default)]
+pub struct PlayerStats {
+    pub rounds_played: u64,
+    pub total_wagered: u128,
+    pub total_won: u128,            // paid back by winning bets, chips included
+    pub biggest_win: u128,          // largest net win of a round
+    pub recent_days: Vec<DayStats>, // days with bets in the last STATS_DAYS days, oldest first
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LeaderboardKind {
+    NetWinnings,
+    Volume,
+}
+
+/*
+rounds, wager and win of a player over a window
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, Default)]
+pub struct WindowStats {
+    pub rounds: u64,
+    pub wagered: u128,
+    pub won: u128,
+}
+
+/*
+top MAX_LEADERBOARD_SIZE players of a period by each kind, updated at settlement so the views never walk all players.
+a player pushed out comes back with the next settled round
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, Default)]
+pub struct Leaderboard {
+    pub by_net: Vec<(AccountId, WindowStats)>,
+    pub by_volume: Vec<(AccountId, WindowStats)>,
+}
+
+impl WindowStats {
+    pub fn net(&self) -> i128 {
+        self.won as i128 - self.wagered as i128
+    }
+
+    pub fn score(&self, kind: LeaderboardKind) -> i128 {
+        match kind {
+            LeaderboardKind::NetWinnings => self.net(),
+            LeaderboardKind::Volume => self.wagered as i128,
+        }
+    }
+}
+
+/*
+keep the ranking sorted by score, a tie keeps the earlier player ahead
+*/
+fn rank(ranking: &mut Vec<(AccountId, WindowStats)>, kind: LeaderboardKind, account_id: &AccountId, stats: &WindowStats) {
+    ranking.retain(|(player_id, _)| player_id != account_id);
+    let score = stats.score(kind);
+    let position = ranking.iter().position(|(_, other)| other.score(kind) < score).unwrap_or(ranking.len());
+    ranking.insert(position, (account_id.clone(), stats.clone()));
+    ranking.truncate(MAX_LEADERBOARD_SIZE as usize);
+}
+
+impl Leaderboard {
+    pub fn update(&mut self, account_id: &AccountId, stats: &WindowStats) {
+        rank(&mut self.by_net, LeaderboardKind::NetWinnings, account_id, stats);
+        rank(&mut self.by_volume, LeaderboardKind::Volume, account_id, stats);
+    }
+
+    pub fn ranking(&self, kind: LeaderboardKind) -> &Vec<(AccountId, WindowStats)> {
+        match kind {
+            LeaderboardKind::NetWinnings => &self.by_net,
+            LeaderboardKind::Volume => &self.by_volume,
+        }
+    }
+}
+
+impl PlayerStats {
+    pub fn record(&mut self, day: u64, bet_amount: u128, win_amount: u128) {
+        self.rounds_played += 1;
+        self.total_wagered = safe_add(self.total_wagered, bet_amount);
+        self.total_won = safe_add(self.total_won, win_amount);
+        self.biggest_win = std::cmp::max(self.biggest_win, win_amount.saturating_sub(bet_amount));
+        self.recent_days.retain(|stats| stats.day + STATS_DAYS > day);
+        if self.recent_days.last().map(|stats| stats.day) != Some(day) {
+            self.recent_days.push(DayStats {
+                day,
+                ..Default::default()
+            });
+        }
+        let today = self.recent_days.last_mut().unwrap();
+        today.rounds += 1;
+        today.wagered = safe_add(today.wagered, bet_amount);
+        today.won = safe_add(today.won, win_amount);
+    }
+
+    /*
+    all time with None, else the last window_days days including today
+    */
+    pub fn window(&self, today: u64, window_days: Option<u64>) -> WindowStats {
+        match window_days {
+            None => WindowStats {
+                rounds: self.rounds_played,
+                wagered: self.total_wagered,
+                won: self.total_won,
+            },
+            Some(days) => self.recent_days.iter()
+                .filter(|stats| stats.day + days > today)
+                .fold(WindowStats::default(), |total, stats| WindowStats {
+                    rounds: total.rounds + stats.rounds,
+                    wagered: safe_add(total.wagered, stats.wagered),
+                    won: safe_add(total.won, stats.won),
+                }),
+        }
+    }
+}
+
+impl Contract {
+    /*
+    record the (player, bet amount, win amount) of a settled round and rank the players,
+    the boards of all time and today are loaded once per round
+    */
+    pub(crate) fn internal_record_stats(&mut self, results: &Vec<(AccountId, u128, u128)>) {
+        if results.len() == 0 {
+            return;
+        }
+        let today = env::block_timestamp() / DAY_NANOS;
+        let mut all_time = self.leaderboards.get(&ALL_TIME).unwrap_or_default();
+        let mut day = self.leaderboards.get(&today).unwrap_or_default();
+        for (account_id, bet_amount, win_amount) in results.iter() {
+            let mut stats = self.player_stats.get(account_id).unwrap_or_default();
+            stats.record(today, *bet_amount, *win_amount);
+            self.player_stats.insert(account_id, &stats);
+            all_time.update(account_id, &stats.window(today, None));
+            day.update(account_id, &stats.window(today, Some(1)));
+        }
+        self.leaderboards.insert(&ALL_TIME, &all_time);
+        self.leaderboards.insert(&today, &day);
+        if self.leaderboard_days.last() != Some(&today) {
+            self.leaderboard_days.push(today);
+        }
+        while self.leaderboard_days[0] + STATS_DAYS <= today {      // the day left every window
+            self.leaderboards.remove(&self.leaderboard_days.remove(0));
+        }
+    }
+
+    /*
+    top players by net winnings or volume. a window takes the players of its day boards as candidates
+    and ranks them by their exact totals over the window
+    */
+    pub(crate) fn cal_leaderboard(&self, kind: LeaderboardKind, window_days: Option<u64>, limit: u32) -> Vec<(AccountId, WindowStats)> {
+        require(window_days.map(|days| days > 0 && days <= STATS_DAYS).unwrap_or(true), RoulefiError::InvalidStatsWindow);
+        let today = env::block_timestamp() / DAY_NANOS;
+        let mut board: Vec<(AccountId, WindowStats)> = match window_days {
+            None => self.leaderboards.get(&ALL_TIME).map(|board| board.ranking(kind).clone()).unwrap_or_default(),
+            Some(days) => {
+                let mut candidates: Vec<AccountId> = Vec::new();
+                for day in self.leaderboard_days.iter().filter(|day| **day + days > today) {
+                    for (account_id, _) in self.leaderboards.get(day).unwrap().ranking(kind).iter() {
+                        if !candidates.contains(account_id) {
+                            candidates.push(account_id.clone());
+                        }
+                    }
+                }
+                let mut players: Vec<(AccountId, WindowStats)> = candidates.into_iter().map(|account_id| {
+                    let stats = self.player_stats.get(&account_id).unwrap_or_default().window(today, Some(days));     // the days off a board count too
+                    (account_id, stats)
+                }).collect();
+                players.sort_by(|a, b| b.1.score(kind).cmp(&a.1.score(kind)));
+                players
+            },
+        };
+        board.truncate(std::cmp::min(limit, MAX_LEADERBOARD_SIZE) as usize);
+        board
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    #[test]
+    fn stats_by_window() {
+        let mut stats = PlayerStats::default();
+        stats.record(100, 10, 0);
+        stats.record(100, 10, 30);
+        stats.record(105, 20, 0);
+        stats.record(140, 5, 0);
+        assert_eq!(stats.rounds_played, 4);
+        assert_eq!(stats.biggest_win, 20);
+        assert_eq!(stats.window(140, None).net(), -15);
+        assert_eq!(stats.recent_days.len(), 1);     // older days left the 30 day window
+        let week = stats.window(140, Some(7));
+        assert_eq!((week.rounds, week.wagered, week.won), (1, 5, 0));
+        assert_eq!(stats.window(146, Some(7)).rounds, 1);
+        assert_eq!(stats.window(147, Some(7)).rounds, 0);
+    }
+
+    #[test]
+    fn leaderboard_ranks_players() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.internal_record_stats(&vec![
+            ("bob".to_string(), 100, 0),
+            ("carol".to_string(), 10, 40),
+        ]);
+        contract.internal_record_stats(&vec![("dave".to_string(), 50, 50)]);
+
+        let board = contract.cal_leaderboard(LeaderboardKind::NetWinnings, None, 10);
+        let ranked: Vec<&str> = board.iter().map(|(account_id, _)| account_id.as_str()).collect();
+        assert_eq!(ranked, vec!["carol", "dave", "bob"]);
+        let board = contract.cal_leaderboard(LeaderboardKind::Volume, Some(1), 2);
+        let ranked: Vec<&str> = board.iter().map(|(account_id, _)| account_id.as_str()).collect();
+        assert_eq!(ranked, vec!["bob", "dave"]);
+    }
+
+    #[test]
+    fn window_counts_days_off_the_board() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.internal_record_stats(&vec![("bob".to_string(), 100, 0)]);
+
+        let mut context = get_context();
+        context.block_timestamp += DAY_NANOS;
+        testing_env!(context);
+        contract.internal_record_stats(&vec![("bob".to_string(), 10, 0)]);
+        let today = env::block_timestamp() / DAY_NANOS;
+        contract.leaderboards.insert(&today, &Leaderboard::default());     // bob was pushed out of today's board
+
+        let board = contract.cal_leaderboard(LeaderboardKind::Volume, Some(2), 10);
+        assert_eq!(board[0].0, "bob");
+        assert_eq!((board[0].1.rounds, board[0].1.wagered), (2, 110));
+    }
+
+    #[test]
+    fn old_days_leave_the_boards() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.internal_record_stats(&vec![("bob".to_string(), 100, 0)]);
+
+        let mut context = get_context();
+        context.block_timestamp += STATS_DAYS * DAY_NANOS;
+        testing_env!(context);
+        contract.internal_record_stats(&vec![("carol".to_string(), 10, 0)]);
+        assert_eq!(contract.leaderboard_days.len(), 1);
+        let board = contract.cal_leaderboard(LeaderboardKind::Volume, Some(STATS_DAYS), 10);
+        assert_eq!(board.len(), 1);
+        assert_eq!(board[0].0, "carol");
+        assert_eq!(contract.cal_leaderboard(LeaderboardKind::Volume, None, 10).len(), 2);
+    }
+}
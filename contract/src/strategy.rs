@@ -0,0 +1,239 @@
+use crate::*;
+
+const MAX_STRATEGY_STEPS: u32 = 30;
+
+/*
+how the wager moves after a round
+  Martingale: doubles after a loss, back to base after a win
+  DAlembert: one more unit after a loss, one less after a win
+  Fibonacci: one step up the sequence after a loss, two steps down after a win
+*/
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Progression {
+    Martingale,
+    DAlembert,
+    Fibonacci,
+}
+
+/*
+a progression system on top of the standing order, every bet of the order takes base_chips times the units of current step
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone)]
+pub struct Strategy {
+    pub progression: Progression,
+    pub base_chips: u128,
+    pub stop_loss: u128,        // stop when the net loss reaches it, 0 for no stop
+    pub take_profit: u128,      // stop when the net profit reaches it, 0 for no stop
+    pub max_steps: u32,         // back to base after max_steps losses up
+    pub step: u32,
+    pub won_amount: u128,       // total win of the rounds played by the strategy
+    pub bet_amount: u128,       // total wager of the rounds played by the strategy
+    pub placed: bool,           // current round's bets are placed by the strategy
+    pub active: bool,           // false after stop loss or take profit
+}
+
+/*
+wager units at a step of the progression
+*/
+pub fn progression_units(progression: Progression, step: u32) -> u128 {
+    match progression {
+        Progression::Martingale => 1u128 << step,
+        Progression::DAlembert => step as u128 + 1,
+        Progression::Fibonacci => {
+            let (mut a, mut b) = (1u128, 1u128);
+            for _ in 0..step {
+                let next = a + b;
+                a = b;
+                b = next;
+            }
+            a
+        },
+    }
+}
+
+impl Strategy {
+    pub fn next_chips(&self) -> u128 {
+        self.base_chips.saturating_mul(progression_units(self.progression, self.step))  // too large chips are rejected by the table limits
+    }
+
+    /*
+    move to the next step by the round result and stop at stop loss or take profit
+    */
+    pub fn settle(&mut self, bet_amount: u128, win_amount: u128) {
+        self.bet_amount = safe_add(self.bet_amount, bet_amount);
+        self.won_amount = safe_add(self.won_amount, win_amount);
+        if win_amount > bet_amount {
+            self.step = match self.progression {
+                Progression::Martingale => 0,
+                Progression::DAlembert => self.step.saturating_sub(1),
+                Progression::Fibonacci => self.step.saturating_sub(2),
+            };
+        } else {
+            self.step += 1;
+            if self.step > self.max_steps {
+                self.step = 0;
+            }
+        }
+        if self.stop_loss > 0 && self.bet_amount >= safe_add(self.won_amount, self.stop_loss) {
+            self.active = false;
+        }
+        if self.take_profit > 0 && self.won_amount >= safe_add(self.bet_amount, self.take_profit) {
+            self.active = false;
+        }
+    }
+}
+
+impl Contract {
+    /*
+    the standing order's bets with the chips of the strategy's current step
+    */
+    pub(crate) fn strategy_bets(&self, account_id: &AccountId, bets: &Vec<Bet>) -> Vec<Bet> {
+        match self.strategies.get(account_id) {
+            Some(strategy) if strategy.active => {
+                let chips = strategy.next_chips();
+                bets.iter().map(|bet| Bet {
+                    bet_type: bet.bet_type,
+                    number: bet.number,
+                    chips,
+                }).collect()
+            },
+            _ => bets.clone(),
+        }
+    }
+
+    pub(crate) fn internal_mark_strategy_placed(&mut self, account_id: &AccountId) {
+        if let Some(mut strategy) = self.strategies.get(account_id) {
+            if strategy.active {
+                strategy.placed = true;
+                self.strategies.insert(account_id, &strategy);
+            }
+        }
+    }
+
+    pub(crate) fn internal_unmark_strategy_placed(&mut self, account_id: &AccountId) {
+        if let Some(mut strategy) = self.strategies.get(account_id) {
+            if strategy.placed {
+                strategy.placed = false;
+                self.strategies.insert(account_id, &strategy);
+            }
+        }
+    }
+
+    /*
+    settle the round of a strategy, the standing order is cancelled when the strategy stops
+    */
+    pub(crate) fn internal_settle_strategy(&mut self, account_id: &AccountId, bet_amount: u128, win_amount: u128) {
+        if let Some(mut strategy) = self.strategies.get(account_id) {
+            if !strategy.placed {
+                return;
+            }
+            strategy.placed = false;
+            strategy.settle(bet_amount, win_amount);
+            if !strategy.active {
+                self.standing_orders.remove(account_id);
+                self.standing_accounts.remove(account_id);
+            }
+            self.strategies.insert(account_id, &strategy);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+    run a progression on the caller's standing order from the next placed round
+    */
+    pub fn set_strategy(&mut self, progression: Progression, base_chips: U128, stop_loss: U128, take_profit: U128, max_steps: u32) {
+        let sender_id = env::predecessor_account_id();
+        require(self.standing_orders.get(&sender_id).is_some(), RoulefiError::StandingOrderNotFound);
+        let base_chips = u128::from(base_chips);
+        require(base_chips > 0 && base_chips % self.config.table_limits.chip_unit == 0, RoulefiError::InvalidChips);
+        require(max_steps <= MAX_STRATEGY_STEPS, RoulefiError::InvalidStrategy);
+        self.strategies.insert(&sender_id, &Strategy {
+            progression,
+            base_chips,
+            stop_loss: stop_loss.into(),
+            take_profit: take_profit.into(),
+            max_steps,
+            step: 0,
+            won_amount: 0,
+            bet_amount: 0,
+            placed: false,
+            active: true,
+        });
+        self.assert_solvent();
+    }
+
+    /*
+    the standing order goes on with its own chips
+    */
+    pub fn clear_strategy(&mut self) {
+        let sender_id = env::predecessor_account_id();
+        require(self.strategies.remove(&sender_id).is_some(), RoulefiError::StrategyNotFound);
+        self.assert_solvent();
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strategy(progression: Progression) -> Strategy {
+        Strategy {
+            progression,
+            base_chips: CHIP_UNIT,
+            stop_loss: CHIP_UNIT * 20,
+            take_profit: 0,
+            max_steps: 3,
+            step: 0,
+            won_amount: 0,
+            bet_amount: 0,
+            placed: true,
+            active: true,
+        }
+    }
+
+    #[test]
+    fn units_by_progression() {
+        let units: Vec<u128> = (0..6).map(|step| progression_units(Progression::Martingale, step)).collect();
+        assert_eq!(units, vec![1, 2, 4, 8, 16, 32]);
+        let units: Vec<u128> = (0..6).map(|step| progression_units(Progression::DAlembert, step)).collect();
+        assert_eq!(units, vec![1, 2, 3, 4, 5, 6]);
+        let units: Vec<u128> = (0..6).map(|step| progression_units(Progression::Fibonacci, step)).collect();
+        assert_eq!(units, vec![1, 1, 2, 3, 5, 8]);
+    }
+
+    #[test]
+    fn martingale_until_stop_loss() {
+        let mut martingale = strategy(Progression::Martingale);
+        martingale.settle(CHIP_UNIT, 0);
+        assert_eq!(martingale.next_chips(), CHIP_UNIT * 2);
+        martingale.settle(CHIP_UNIT * 2, CHIP_UNIT * 4);
+        assert_eq!(martingale.next_chips(), CHIP_UNIT);
+
+        for _ in 0..4 {
+            let chips = martingale.next_chips();
+            martingale.settle(chips, 0);
+        }
+        assert_eq!(martingale.step, 0);     // back to base after max_steps losses
+        assert!(martingale.active);
+        martingale.settle(CHIP_UNIT * 6, 0);
+        assert!(!martingale.active);        // 1 + 2 + 15 + 6 bet, 4 won
+    }
+
+    #[test]
+    fn fibonacci_steps_back_two() {
+        let mut fibonacci = strategy(Progression::Fibonacci);
+        fibonacci.stop_loss = 0;
+        for _ in 0..3 {
+            fibonacci.settle(CHIP_UNIT, 0);
+        }
+        assert_eq!(fibonacci.next_chips(), CHIP_UNIT * 3);
+        fibonacci.settle(CHIP_UNIT * 3, CHIP_UNIT * 6);
+        assert_eq!(fibonacci.next_chips(), CHIP_UNIT);
+    }
+}
@@ -0,0 +1,479 @@
+use crate::*;
+use crate::view::BetInfo;
+use crate::limits::LimitKind;
+use crate::tournament::settle_entry;
+use near_sdk::json_types::ValidAccountId;
+
+const MAX_TABLE_PLAYERS: usize = 50;         // allowlist size, the round's bets live in the table record every table_bet loads and spin_table settles at once
+const MAX_HOST_BPS: u32 = 5000;
+
+/*
+a private table with its own rounds, only allowlisted accounts can bet.
+a shared table plays against the staking pool within the main table limits, a separate one against the host's bankroll
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub host_id: AccountId,
+    pub allowlist: Vec<AccountId>,
+    pub limits: TableLimits,
+    pub round_delta: u64,
+    pub host_bps: u32,                      // host's part of the table's house profit, in basis points
+    pub shared_bankroll: bool,
+    pub bankroll_amount: u128,              // separate bankroll, 0 for a shared table
+    pub round_index: u64,
+    pub next_round_block_index: u64,
+    pub bets: Vec<(AccountId, Vec<Bet>)>,   // bets of current round
+    pub bet_amount: u128,                   // chips of current round
+    pub payout_amount: u128,                // max payout of current round, it must fit in a separate bankroll
+    pub last_number: Option<u8>,
+    pub closed: bool,
+    pub tournament_id: Option<u64>,         // the tournament played on the table, its bets are tournament chips
+}
+
+impl Table {
+    pub fn round_state(&self, bet_close_delta: u64) -> RoundState {
+        let block_index = env::block_index();
+        if block_index > self.next_round_block_index {
+            RoundState::Spinning
+        } else if block_index > self.next_round_block_index.saturating_sub(bet_close_delta) {
+            RoundState::Closed
+        } else {
+            RoundState::Open
+        }
+    }
+
+    /*
+    the same as the main round, the first bet of a round nobody bet in time starts the countdown again
+    */
+    pub(crate) fn open_round(&mut self, bet_close_delta: u64) {
+        if self.bets.len() == 0 && self.round_state(bet_close_delta) != RoundState::Open {
+            self.next_round_block_index = env::block_index() + self.round_delta;
+        }
+        require(self.round_state(bet_close_delta) == RoundState::Open, RoulefiError::BettingClosed);
+    }
+
+    pub(crate) fn next_round(&mut self) {
+        self.bets.clear();
+        self.bet_amount = 0;
+        self.payout_amount = 0;
+        self.round_index += 1;
+        self.next_round_block_index = env::block_index() + self.round_delta;
+    }
+}
+
+impl Contract {
+    fn assert_table_host(&self, table: &Table) {
+        require(env::predecessor_account_id() == table.host_id, RoulefiError::HostOnly);
+        require(table.tournament_id.is_none(), RoulefiError::InvalidTable);     // a tournament table is run by its tournament
+    }
+
+    /*
+    the owner's table a tournament is played on, nobody bets on it but the tournament players
+    */
+    pub(crate) fn internal_create_tournament_table(&mut self, tournament_id: u64) -> u64 {
+        self.tables.push(&Table {
+            host_id: self.owner_id.clone(),
+            allowlist: Vec::new(),
+            limits: self.config.table_limits.clone(),
+            round_delta: self.config.round_delta,
+            host_bps: 0,
+            shared_bankroll: false,
+            bankroll_amount: 0,
+            round_index: 0,
+            next_round_block_index: env::block_index() + self.config.round_delta,
+            bets: Vec::new(),
+            bet_amount: 0,
+            payout_amount: 0,
+            last_number: None,
+            closed: false,
+            tournament_id: Some(tournament_id),
+        });
+        self.tables.len() - 1
+    }
+
+    /*
+    the bet limits of a table follow the chip unit of the main table
+    */
+    fn table_limits(&self, min_chips: Vec<U128>, max_chips: Vec<U128>, shared_bankroll: bool) -> TableLimits {
+        require(min_chips.len() == PAYOUTS.len() && max_chips.len() == PAYOUTS.len(), RoulefiError::InvalidLimitsLength);
+        let chip_unit = self.config.table_limits.chip_unit;
+        let min_chips: Vec<u128> = min_chips.into_iter().map(|chips| u128::from(chips)).collect();
+        let max_chips: Vec<u128> = max_chips.into_iter().map(|chips| u128::from(chips)).collect();
+        for i in 0..min_chips.len() {
+            require(min_chips[i] > 0 && min_chips[i] <= max_chips[i], RoulefiError::InvalidLimits);
+            require(min_chips[i] % chip_unit == 0 && max_chips[i] % chip_unit == 0, RoulefiError::InvalidChips);
+            require(!shared_bankroll || max_chips[i] <= self.config.table_limits.max_chips[i], RoulefiError::InvalidLimits);   // stakers take no more risk than on the main table
+        }
+        TableLimits {
+            min_chips,
+            max_chips,
+            chip_unit,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+    the owner creates any table, other hosts pay table_creation_fee to treasury and bring a separate bankroll with the rest of the deposit
+    */
+    #[payable]
+    pub fn create_table(&mut self, allowlist: Vec<ValidAccountId>, min_chips: Vec<U128>, max_chips: Vec<U128>, round_delta: u64, host_bps: u32, shared_bankroll: bool) -> U64 {
+        let host_id = env::predecessor_account_id();
+        let mut deposit = env::attached_deposit();
+        if host_id != self.owner_id {
+            require(!shared_bankroll, RoulefiError::OwnerOnly);
+            require(deposit >= self.config.table_creation_fee, RoulefiError::TableFeeNotPaid);
+            deposit -= self.config.table_creation_fee;
+            self.treasury_status.treasury_amount = safe_add(self.treasury_status.treasury_amount, self.config.table_creation_fee);
+        }
+        require(allowlist.len() <= MAX_TABLE_PLAYERS, RoulefiError::InvalidTable);
+        require(round_delta > self.config.bet_close_delta && host_bps <= MAX_HOST_BPS, RoulefiError::InvalidTable);
+        let limits = self.table_limits(min_chips, max_chips, shared_bankroll);
+        let mut bankroll_amount = 0;
+        if shared_bankroll {        // a shared table doesn't hold funds
+            let mut host = self.accounts.get(&host_id).unwrap_or(new_user());
+            host.balance = safe_add(host.balance, deposit);
+            self.accounts.insert(&host_id, &host);
+        } else {
+            bankroll_amount = deposit;
+        }
+        self.tables.push(&Table {
+            host_id,
+            allowlist: allowlist.into_iter().map(|account_id| account_id.into()).collect(),
+            limits,
+            round_delta,
+            host_bps,
+            shared_bankroll,
+            bankroll_amount,
+            round_index: 0,
+            next_round_block_index: env::block_index() + round_delta,
+            bets: Vec::new(),
+            bet_amount: 0,
+            payout_amount: 0,
+            last_number: None,
+            closed: false,
+            tournament_id: None,
+        });
+        self.assert_solvent();
+        U64::from(self.tables.len() - 1)
+    }
+
+    /*
+    replace the allowlist, bets already placed stay in the round
+    */
+    pub fn set_table_allowlist(&mut self, table_id: U64, allowlist: Vec<ValidAccountId>) {
+        let table_id = u64::from(table_id);
+        let mut table = self.tables.get(table_id).or_fail(RoulefiError::TableNotFound);
+        self.assert_table_host(&table);
+        require(allowlist.len() <= MAX_TABLE_PLAYERS, RoulefiError::InvalidTable);
+        table.allowlist = allowlist.into_iter().map(|account_id| account_id.into()).collect();
+        self.tables.replace(table_id, &table);
+        self.assert_solvent();
+    }
+
+    /*
+    add the attached deposit to a separate bankroll
+    */
+    #[payable]
+    pub fn fund_table(&mut self, table_id: U64) {
+        let table_id = u64::from(table_id);
+        let mut table = self.tables.get(table_id).or_fail(RoulefiError::TableNotFound);
+        self.assert_table_host(&table);
+        require(!table.closed, RoulefiError::TableClosed);
+        require(!table.shared_bankroll, RoulefiError::InvalidTable);
+        table.bankroll_amount = safe_add(table.bankroll_amount, env::attached_deposit());
+        self.tables.replace(table_id, &table);
+        self.assert_solvent();
+    }
+
+    /*
+    close the table between rounds, a separate bankroll goes back to the host's balance
+    */
+    pub fn close_table(&mut self, table_id: U64) {
+        let table_id = u64::from(table_id);
+        let mut table = self.tables.get(table_id).or_fail(RoulefiError::TableNotFound);
+        self.assert_table_host(&table);
+        require(!table.closed, RoulefiError::TableClosed);
+        require(table.bets.len() == 0, RoulefiError::TableRoundInPlay);
+        let mut host = self.accounts.get(&table.host_id).unwrap_or(new_user());
+        host.balance = safe_add(host.balance, table.bankroll_amount);
+        self.accounts.insert(&table.host_id, &host);
+        table.bankroll_amount = 0;
+        table.closed = true;
+        self.tables.replace(table_id, &table);
+        self.assert_solvent();
+    }
+
+    /*
+    place the bets of an allowlisted player in current round of the table
+    */
+    #[payable]
+    pub fn table_bet(&mut self, table_id: U64, bets: Vec<BetInfo>, round_index: U64) {
+        let table_id = u64::from(table_id);
+        let mut table = self.tables.get(table_id).or_fail(RoulefiError::TableNotFound);
+        require(!table.closed, RoulefiError::TableClosed);
+        require(table.tournament_id.is_none(), RoulefiError::InvalidTable);      // tournament chips are bet with tournament_bet
+        require(table.round_index == u64::from(round_index), RoulefiError::WrongRoundIndex);
+        table.open_round(self.config.bet_close_delta);
+        let sender_id = env::predecessor_account_id();
+        require(table.allowlist.contains(&sender_id), RoulefiError::NotAllowlisted);
+        require(table.bets.iter().all(|(player_id, _)| *player_id != sender_id), RoulefiError::AlreadyBet);
+        self.assert_not_excluded(&sender_id);
+        let mut account = self.accounts.get(&sender_id).unwrap_or(new_user());
+        account.balance = safe_add(account.balance, env::attached_deposit());
+        self.internal_use_limit(&sender_id, LimitKind::Deposit, env::attached_deposit());
+
+        let bets = to_bets(&bets);
+        let total = validate_bets(&table.limits, &Vec::new(), &bets).or_fail();
+        let payout = bets.iter().fold(0, |payout, item| safe_add(payout, safe_mul(PAYOUTS[item.bet_type as usize] as u128, item.chips)));
+        require(account.balance >= total, RoulefiError::NotEnoughBalance);
+        if table.shared_bankroll {
+            self.check_pool_exposure(total).or_fail();
+            self.round_status.table_bet_amount = safe_add(self.round_status.table_bet_amount, total);
+        } else {
+            require(safe_add(table.payout_amount, payout) <= table.bankroll_amount, RoulefiError::ExceedMaxAmountAllowed);
+        }
+        self.check_limit(&sender_id, LimitKind::Loss, total).or_fail();
+        self.internal_use_limit(&sender_id, LimitKind::Wager, total);
+        account.balance -= total;
+        self.assert_gas_reserved(&account);
+        self.accounts.insert(&sender_id, &account);
+        table.bets.push((sender_id, bets));
+        table.bet_amount = safe_add(table.bet_amount, total);
+        table.payout_amount = safe_add(table.payout_amount, payout);
+        self.tables.replace(table_id, &table);
+        self.assert_solvent();
+    }
+
+    /*
+    anyone can spin a table after its next_round_block_index, the caller gets gas_per_player from every player as keeper reward
+    */
+    pub fn spin_table(&mut self, table_id: U64, round_index: U64) {
+        let table_id = u64::from(table_id);
+        let mut table = self.tables.get(table_id).or_fail(RoulefiError::TableNotFound);
+        require(table.round_index == u64::from(round_index), RoulefiError::WrongRoundIndex);
+        require(env::block_index() > table.next_round_block_index, RoulefiError::TooQuickToSpin);
+        require(table.bets.len() > 0, RoulefiError::RoundWithoutBets);
+        let (last_player_id, last_bets) = table.bets.last().unwrap();
+        let number = wheel_number(last_player_id, last_bets.last().unwrap(), table_id.to_be_bytes().to_vec());
+
+        let mut total_bet: u128 = 0;
+        let mut total_win: u128 = 0;
+        let mut keeper_reward: u128 = 0;
+        let mut results: Vec<(AccountId, u128, u128)> = Vec::new();
+        for (player_id, bets) in table.bets.iter() {
+            let mut account = self.accounts.get(player_id).or_fail(RoulefiError::AccountNotFound);
+            let mut bet_amount = 0;
+            let mut win_amount = 0;
+            for b in bets.iter() {
+                bet_amount = safe_add(bet_amount, b.chips);
+                if check_win(number, b) {
+                    win_amount = safe_add(win_amount, safe_mul(PAYOUTS[b.bet_type as usize] as u128, b.chips));
+                }
+            }
+            if table.tournament_id.is_some() {
+                settle_entry(&mut account, win_amount);     // tournament chips never touch a bankroll or the pool
+            } else {
+                account.balance = safe_add(account.balance, win_amount);
+            }
+            let fee = std::cmp::min(self.config.gas_per_player, account.balance);   // keeper fee
+            account.balance -= fee;
+            keeper_reward = safe_add(keeper_reward, fee);
+            if table.tournament_id.is_some() {
+                self.accounts.insert(player_id, &account);
+                continue;
+            }
+            total_bet = safe_add(total_bet, bet_amount);
+            total_win = safe_add(total_win, win_amount);
+            self.accounts.insert(player_id, &account);
+            self.internal_record_loss(player_id, bet_amount, win_amount);
+            results.push((player_id.clone(), bet_amount, win_amount));
+        }
+        self.internal_record_stats(&results);
+        let commission = safe_mul_div(total_bet.saturating_sub(total_win), table.host_bps as u128, BPS_DENOMINATOR as u128);
+        if commission > 0 {
+            let mut host = self.accounts.get(&table.host_id).unwrap_or(new_user());
+            host.balance = safe_add(host.balance, commission);
+            self.accounts.insert(&table.host_id, &host);
+        }
+        if table.shared_bankroll {
+            self.round_status.table_bet_amount = safe_sub(self.round_status.table_bet_amount, table.bet_amount);
+            self.cal_profit(total_bet, safe_add(total_win, commission));
+        } else {
+            table.bankroll_amount = safe_sub(safe_add(table.bankroll_amount, total_bet), safe_add(total_win, commission));
+        }
+        let mut wheel_stats = self.table_wheel_stats.get(&table_id).unwrap_or_else(WheelStats::new);
+        wheel_stats.record(number, self.config.wheel_window);
+        self.table_wheel_stats.insert(&table_id, &wheel_stats);
+        table.last_number = Some(number);
+        table.next_round();
+        self.tables.replace(table_id, &table);
+        if keeper_reward > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(keeper_reward);
+        }
+        self.assert_solvent();
+    }
+
+    /*
+    if nobody spins the table in refund_delay blocks after next_round_block_index, anyone can give the bets back
+    */
+    pub fn refund_table_round(&mut self, table_id: U64, round_index: U64) {
+        let table_id = u64::from(table_id);
+        let mut table = self.tables.get(table_id).or_fail(RoulefiError::TableNotFound);
+        require(table.round_index == u64::from(round_index), RoulefiError::WrongRoundIndex);
+        require(table.bets.len() > 0, RoulefiError::RoundWithoutBets);
+        require(env::block_index() > table.next_round_block_index + self.config.refund_delay, RoulefiError::TooQuickToRefund);
+        for (player_id, bets) in table.bets.iter() {
+            let mut account = self.accounts.get(player_id).or_fail(RoulefiError::AccountNotFound);
+            let chips = bets.iter().fold(0, |total, bet| safe_add(total, bet.chips));
+            if table.tournament_id.is_some() {
+                settle_entry(&mut account, chips);
+            } else {
+                account.balance = safe_add(account.balance, chips);
+            }
+            self.accounts.insert(player_id, &account);
+        }
+        if table.shared_bankroll {
+            self.round_status.table_bet_amount = safe_sub(self.round_status.table_bet_amount, table.bet_amount);
+        }
+        table.next_round();
+        self.tables.replace(table_id, &table);
+        self.assert_solvent();
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+    use near_sdk::serde::export::TryFrom;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    fn stake_pool(contract: &mut Contract) {
+        let mut context = get_context();
+        context.predecessor_account_id = "alice".to_string();
+        context.attached_deposit = CHIP_UNIT * 100000;   // 1000 NEAR
+        testing_env!(context);
+        contract.stake();
+    }
+
+    fn red(chips: u128) -> Vec<BetInfo> {
+        vec![BetInfo {
+            bet_type: 0,
+            number: 1,
+            chips: U128::from(chips)
+        }]
+    }
+
+    fn vip_table(contract: &mut Contract) {
+        let mut context = get_context();
+        context.predecessor_account_id = "carol".to_string();
+        context.attached_deposit = contract.config.table_creation_fee + CHIP_UNIT * 1000;
+        testing_env!(context);
+        contract.create_table(
+            vec![ValidAccountId::try_from("bob").unwrap()],
+            vec![U128::from(CHIP_UNIT * 10); 6],
+            vec![U128::from(CHIP_UNIT * 100); 6],
+            20,
+            1000,
+            false
+        );
+    }
+
+    #[test]
+    fn separate_table_round() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        vip_table(&mut contract);
+        assert_eq!(contract.treasury_status.treasury_amount, contract.config.table_creation_fee);
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.table_bet(U64::from(0), red(CHIP_UNIT * 50), U64::from(0));
+        assert_eq!(contract.tables.get(0).unwrap().payout_amount, CHIP_UNIT * 100);
+
+        let mut context = get_context();
+        context.block_index = 21;
+        testing_env!(context);
+        contract.spin_table(U64::from(0), U64::from(0));
+        let table = contract.tables.get(0).unwrap();
+        assert_eq!(table.round_index, 1);
+        let bob = contract.accounts.get(&"bob".to_string()).unwrap();
+        let carol = contract.accounts.get(&"carol".to_string()).map(|account| account.balance).unwrap_or(0);
+        // chips only move between the player, the bankroll and the host
+        assert_eq!(bob.balance + contract.config.gas_per_player + table.bankroll_amount + carol, CHIP_UNIT * 1100);
+        assert_eq!(contract.wheel_stats_of(Some(0)).recent, vec![table.last_number.unwrap()]);
+        assert!(contract.wheel_stats.recent.is_empty());       // the main wheel doesn't count table spins
+    }
+
+    #[test]
+    #[should_panic(expected = "not on the allowlist")]
+    fn table_needs_allowlist() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        vip_table(&mut contract);
+
+        let mut context = get_context();
+        context.predecessor_account_id = "dave".to_string();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.table_bet(U64::from(0), red(CHIP_UNIT * 50), U64::from(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceed max bet amount allowed")]
+    fn shared_tables_share_pool_exposure() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        stake_pool(&mut contract);      // max_amount_allowed is 10000 chips
+
+        let context = get_context();
+        testing_env!(context);
+        for _ in 0..2 {
+            contract.create_table(
+                vec![ValidAccountId::try_from("bob").unwrap()],
+                vec![U128::from(CHIP_UNIT); 6],
+                vec![U128::from(CHIP_UNIT * 10000); 6],
+                20,
+                0,
+                true
+            );
+        }
+
+        let mut context = get_context();
+        context.attached_deposit = CHIP_UNIT * 6001;
+        testing_env!(context);
+        contract.table_bet(U64::from(0), red(CHIP_UNIT * 6000), U64::from(0));
+        assert_eq!(contract.round_status.table_bet_amount, CHIP_UNIT * 6000);
+        contract.table_bet(U64::from(1), red(CHIP_UNIT * 6000), U64::from(0));
+    }
+}
@@ -0,0 +1,382 @@
+use crate::*;
+use crate::view::BetInfo;
+use crate::limits::LimitKind;
+
+const MAX_TOURNAMENT_PLAYERS: u32 = 100;     // finish_tournament loads, sorts and pays every entry in one call
+
+/*
+a player's seat in a tournament, tournament chips are only a score and never leave the tournament
+*/
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug, Clone)]
+pub struct TournamentEntry {
+    pub tournament_id: u64,
+    pub chips: u128,
+    pub bet_chips: u128,        // chips on the current round of the tournament table
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone)]
+pub struct TournamentResult {
+    pub account_id: AccountId,
+    pub chips: u128,
+    pub prize: u128,
+}
+
+/*
+a time-boxed tournament played on a table of its own, the entry fees make the prize pool
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub table_id: u64,
+    pub entry_fee: u128,
+    pub starting_chips: u128,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub payout_bps: Vec<u32>,           // prize pool shares by rank
+    pub max_players: u32,
+    pub prize_pool: u128,
+    pub players: Vec<AccountId>,
+    pub results: Vec<TournamentResult>, // ranked when finished
+    pub finished: bool,
+}
+
+/*
+chips of the entry including the ones on the table
+*/
+pub(crate) fn entry_chips(account: &Account) -> u128 {
+    match &account.tournament {
+        Some(entry) => safe_add(entry.chips, entry.bet_chips),
+        None => 0,
+    }
+}
+
+/*
+chips back to the entry when a round of the tournament table is settled or refunded
+*/
+pub(crate) fn settle_entry(account: &mut Account, chips: u128) {
+    if let Some(entry) = account.tournament.as_mut() {
+        entry.chips = safe_add(entry.chips, chips);
+        entry.bet_chips = 0;
+    }
+}
+
+/*
+prizes by rank, places without a player don't take a share
+*/
+pub fn tournament_prizes(prize_pool: u128, payout_bps: &Vec<u32>, player_count: usize) -> Vec<u128> {
+    let places = std::cmp::min(payout_bps.len(), player_count);
+    let total_bps = payout_bps[..places].iter().fold(0u128, |total, bps| safe_add(total, *bps as u128));
+    let mut prizes: Vec<u128> = Vec::new();
+    let mut paid: u128 = 0;
+    for place in 0..places {
+        let prize = if place == places - 1 {
+            safe_sub(prize_pool, paid)       // the last place takes the rounding dust
+        } else {
+            safe_mul_div(prize_pool, payout_bps[place] as u128, total_bps)
+        };
+        paid = safe_add(paid, prize);
+        prizes.push(prize);
+    }
+    prizes
+}
+
+impl Contract {
+    /*
+    players ranked by chips, earlier entries first on a tie
+    */
+    pub(crate) fn tournament_standings(&self, tournament: &Tournament) -> Vec<(AccountId, u128)> {
+        let mut standings: Vec<(AccountId, u128)> = tournament.players.iter().map(|player_id| {
+            let chips = self.accounts.get(player_id).map(|account| entry_chips(&account)).unwrap_or(0);
+            (player_id.clone(), chips)
+        }).collect();
+        standings.sort_by(|a, b| b.1.cmp(&a.1));
+        standings
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+    open a tournament starting start_delay seconds later for duration seconds on a new table with the main table limits,
+    returns the tournament id
+    */
+    pub fn create_tournament(&mut self, entry_fee: U128, starting_chips: U128, start_delay: U64, duration: U64, payout_bps: Vec<u32>, max_players: u32) -> U64 {
+        self.assert_owner();
+        let starting_chips = u128::from(starting_chips);
+        require(starting_chips > 0 && starting_chips % self.config.table_limits.chip_unit == 0, RoulefiError::InvalidChips);
+        require(u64::from(duration) > 0, RoulefiError::InvalidTournament);
+        require(max_players > 0 && max_players <= MAX_TOURNAMENT_PLAYERS, RoulefiError::InvalidTournament);
+        require(payout_bps.len() > 0 && payout_bps.len() <= max_players as usize, RoulefiError::InvalidTournament);
+        require(payout_bps.iter().fold(0u64, |total, bps| total + *bps as u64) == BPS_DENOMINATOR as u64, RoulefiError::InvalidTournament);
+        let start_time = safe_time_after(env::block_timestamp(), start_delay.into());    // in seconds
+        let table_id = self.internal_create_tournament_table(self.tournaments.len());
+        self.tournaments.push(&Tournament {
+            table_id,
+            entry_fee: entry_fee.into(),
+            starting_chips,
+            start_time,
+            end_time: safe_time_after(start_time, duration.into()),
+            payout_bps,
+            max_players,
+            prize_pool: 0,
+            players: Vec::new(),
+            results: Vec::new(),
+            finished: false,
+        });
+        self.assert_solvent();
+        U64::from(self.tournaments.len() - 1)
+    }
+
+    /*
+    pay the entry fee from balance and the attached deposit, a player is in one tournament at a time
+    */
+    #[payable]
+    pub fn enter_tournament(&mut self, tournament_id: U64) {
+        let sender_id = env::predecessor_account_id();
+        let tournament_id = u64::from(tournament_id);
+        let mut tournament = self.tournaments.get(tournament_id).or_fail(RoulefiError::TournamentNotFound);
+        require(!tournament.finished && env::block_timestamp() < tournament.end_time, RoulefiError::TournamentClosed);
+        require(tournament.players.len() < tournament.max_players as usize, RoulefiError::TournamentFull);
+        self.assert_not_excluded(&sender_id);
+        let mut account = self.accounts.get(&sender_id).unwrap_or(new_user());
+        require(account.tournament.is_none(), RoulefiError::AlreadyInTournament);
+        account.balance = safe_add(account.balance, env::attached_deposit());
+        self.internal_use_limit(&sender_id, LimitKind::Deposit, env::attached_deposit());
+        require(account.balance >= tournament.entry_fee, RoulefiError::NotEnoughBalance);
+        self.check_limit(&sender_id, LimitKind::Loss, tournament.entry_fee).or_fail();     // the entry fee can be lost
+        self.internal_use_limit(&sender_id, LimitKind::Wager, tournament.entry_fee);
+        account.balance -= tournament.entry_fee;
+        account.tournament = Some(TournamentEntry {
+            tournament_id,
+            chips: tournament.starting_chips,
+            bet_chips: 0,
+        });
+        self.accounts.insert(&sender_id, &account);
+        tournament.prize_pool = safe_add(tournament.prize_pool, tournament.entry_fee);
+        tournament.players.push(sender_id);
+        self.tournaments.replace(tournament_id, &tournament);
+        self.assert_solvent();
+    }
+
+    /*
+    bet tournament chips in current round of the tournament table, round_index is the table's.
+    the keeper fee is still paid from balance
+    */
+    pub fn tournament_bet(&mut self, bets: Vec<BetInfo>, round_index: U64) {
+        let sender_id = env::predecessor_account_id();
+        self.assert_not_excluded(&sender_id);
+        let mut account = self.accounts.get(&sender_id).or_fail(RoulefiError::AccountNotFound);
+        let mut entry = account.tournament.clone().or_fail(RoulefiError::NotInTournament);
+        let tournament = self.tournaments.get(entry.tournament_id).or_fail(RoulefiError::TournamentNotFound);
+        let now = env::block_timestamp();
+        require(now >= tournament.start_time && now < tournament.end_time, RoulefiError::TournamentClosed);
+        let mut table = self.tables.get(tournament.table_id).or_fail(RoulefiError::TableNotFound);
+        require(table.round_index == u64::from(round_index), RoulefiError::WrongRoundIndex);
+        table.open_round(self.config.bet_close_delta);
+        require(entry.bet_chips == 0, RoulefiError::AlreadyBet);
+        let bets = to_bets(&bets);
+        let total = validate_bets(&table.limits, &Vec::new(), &bets).or_fail();
+        require(entry.chips >= total, RoulefiError::NotEnoughTournamentChips);
+        self.assert_gas_reserved(&account);
+        entry.chips -= total;
+        entry.bet_chips = total;
+        account.tournament = Some(entry);
+        self.accounts.insert(&sender_id, &account);
+        table.bets.push((sender_id, bets));
+        table.bet_amount = safe_add(table.bet_amount, total);
+        self.tables.replace(tournament.table_id, &table);
+        self.assert_solvent();
+    }
+
+    /*
+    rank the players and pay the prize pool to their balance, anyone can call after the end.
+    bets still on the tournament table go back to the entries and the table is closed
+    */
+    pub fn finish_tournament(&mut self, tournament_id: U64) {
+        let tournament_id = u64::from(tournament_id);
+        let mut tournament = self.tournaments.get(tournament_id).or_fail(RoulefiError::TournamentNotFound);
+        require(!tournament.finished, RoulefiError::TournamentClosed);
+        require(env::block_timestamp() >= tournament.end_time, RoulefiError::TournamentNotOver);
+        let mut table = self.tables.get(tournament.table_id).or_fail(RoulefiError::TableNotFound);
+        for (player_id, bets) in table.bets.iter() {
+            let mut account = self.accounts.get(player_id).or_fail(RoulefiError::AccountNotFound);
+            settle_entry(&mut account, bets.iter().fold(0, |total, bet| safe_add(total, bet.chips)));
+            self.accounts.insert(player_id, &account);
+        }
+        table.next_round();
+        table.closed = true;
+        self.tables.replace(tournament.table_id, &table);
+        let standings = self.tournament_standings(&tournament);
+        let prizes = tournament_prizes(tournament.prize_pool, &tournament.payout_bps, standings.len());
+        for (rank, (player_id, chips)) in standings.into_iter().enumerate() {
+            let prize = prizes.get(rank).cloned().unwrap_or(0);
+            let mut account = self.accounts.get(&player_id).or_fail(RoulefiError::AccountNotFound);
+            account.balance = safe_add(account.balance, prize);
+            account.tournament = None;
+            self.accounts.insert(&player_id, &account);
+            self.internal_record_loss(&player_id, tournament.entry_fee, prize);
+            tournament.results.push(TournamentResult {
+                account_id: player_id,
+                chips,
+                prize,
+            });
+        }
+        tournament.finished = true;
+        self.tournaments.replace(tournament_id, &tournament);
+        self.assert_solvent();
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    fn enter(contract: &mut Contract, account_id: &str) {
+        let mut context = get_context();
+        context.predecessor_account_id = account_id.to_string();
+        context.attached_deposit = CHIP_UNIT * 100;
+        testing_env!(context);
+        contract.enter_tournament(U64::from(0));
+    }
+
+    fn red(chips: u128) -> Vec<BetInfo> {
+        vec![BetInfo {
+            bet_type: 0,
+            number: 1,
+            chips: U128::from(chips)
+        }]
+    }
+
+    #[test]
+    fn prizes_by_rank() {
+        assert_eq!(tournament_prizes(1000, &vec![5000, 3000, 2000], 3), vec![500, 300, 200]);
+        assert_eq!(tournament_prizes(1000, &vec![5000, 3000, 2000], 2), vec![625, 375]);
+        assert_eq!(tournament_prizes(1000, &vec![3333, 3333, 3334], 3), vec![333, 333, 334]);
+        assert_eq!(tournament_prizes(1000, &vec![5000, 5000], 0), Vec::<u128>::new());
+    }
+
+    #[test]
+    fn tournament_pays_by_chips() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.create_tournament(U128::from(CHIP_UNIT * 10), U128::from(CHIP_UNIT * 100), U64::from(0), U64::from(3600), vec![7000, 3000], 10);
+        enter(&mut contract, "bob");
+        enter(&mut contract, "carol");
+        enter(&mut contract, "dave");
+        let mut carol = contract.accounts.get(&"carol".to_string()).unwrap();
+        carol.tournament.as_mut().unwrap().chips = CHIP_UNIT * 150;
+        contract.accounts.insert(&"carol".to_string(), &carol);
+        let mut dave = contract.accounts.get(&"dave".to_string()).unwrap();
+        dave.tournament.as_mut().unwrap().chips = CHIP_UNIT * 50;
+        contract.accounts.insert(&"dave".to_string(), &dave);
+
+        let mut context = get_context();
+        context.block_timestamp += 3600 * 1000000000;
+        testing_env!(context);
+        contract.finish_tournament(U64::from(0));
+        let tournament = contract.tournaments.get(0).unwrap();
+        let ranked: Vec<AccountId> = tournament.results.iter().map(|result| result.account_id.clone()).collect();
+        assert_eq!(ranked, vec!["carol".to_string(), "bob".to_string(), "dave".to_string()]);
+        assert_eq!(contract.accounts.get(&"carol".to_string()).unwrap().balance, CHIP_UNIT * 90 + CHIP_UNIT * 21);
+        assert_eq!(contract.accounts.get(&"bob".to_string()).unwrap().balance, CHIP_UNIT * 90 + CHIP_UNIT * 9);
+        assert_eq!(contract.accounts.get(&"dave".to_string()).unwrap().balance, CHIP_UNIT * 90);
+        assert!(contract.accounts.get(&"dave".to_string()).unwrap().tournament.is_none());
+    }
+
+    #[test]
+    fn tournament_played_on_its_table() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.create_tournament(U128::from(CHIP_UNIT * 10), U128::from(CHIP_UNIT * 100), U64::from(0), U64::from(3600), vec![10000], 10);
+        enter(&mut contract, "bob");
+        let table_id = contract.tournaments.get(0).unwrap().table_id;
+
+        let context = get_context();
+        testing_env!(context);
+        contract.tournament_bet(red(CHIP_UNIT * 50), U64::from(0));
+        assert_eq!(contract.bet_accounts.len(), 0);            // nothing in the main round
+        assert_eq!(contract.tables.get(table_id).unwrap().bets.len(), 1);
+        assert_eq!(entry_chips(&contract.accounts.get(&"bob".to_string()).unwrap()), CHIP_UNIT * 100);
+
+        let mut context = get_context();
+        context.block_index = 61;
+        testing_env!(context);
+        contract.spin_table(U64::from(table_id), U64::from(0));
+        let bob = contract.accounts.get(&"bob".to_string()).unwrap();
+        let entry = bob.tournament.clone().unwrap();
+        assert_eq!(entry.bet_chips, 0);
+        assert!(entry.chips == CHIP_UNIT * 50 || entry.chips == CHIP_UNIT * 150);
+        assert_eq!(bob.balance, CHIP_UNIT * 90 - contract.config.gas_per_player);     // only the keeper fee is near
+    }
+
+    #[test]
+    fn finish_refunds_bets_in_play() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.create_tournament(U128::from(CHIP_UNIT * 10), U128::from(CHIP_UNIT * 100), U64::from(0), U64::from(3600), vec![10000], 10);
+        enter(&mut contract, "bob");
+        let table_id = contract.tournaments.get(0).unwrap().table_id;
+        let context = get_context();
+        testing_env!(context);
+        contract.tournament_bet(red(CHIP_UNIT * 50), U64::from(0));
+
+        let mut context = get_context();
+        context.block_timestamp += 3600 * 1000000000;
+        testing_env!(context);
+        contract.finish_tournament(U64::from(0));
+        let tournament = contract.tournaments.get(0).unwrap();
+        assert_eq!(tournament.results[0].chips, CHIP_UNIT * 100);
+        let table = contract.tables.get(table_id).unwrap();
+        assert!(table.closed);
+        assert_eq!(table.bets.len(), 0);
+        assert_eq!(contract.accounts.get(&"bob".to_string()).unwrap().balance, CHIP_UNIT * 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "not enough tournament chips")]
+    fn tournament_bet_within_chips() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.create_tournament(U128::from(CHIP_UNIT * 10), U128::from(CHIP_UNIT * 100), U64::from(0), U64::from(3600), vec![10000], 10);
+        enter(&mut contract, "bob");
+        let context = get_context();
+        testing_env!(context);
+        contract.tournament_bet(vec![BetInfo {
+            bet_type: 0,
+            number: 1,
+            chips: U128::from(CHIP_UNIT * 200),
+        }], U64::from(0));
+    }
+}
@@ -1,40 +1,414 @@
 use crate::*;
 
+/*
+team share of a distribution, released linearly after the cliff
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone)]
+pub struct VestingGrant {
+    pub amount: u128,
+    pub released: u128,
+    pub start_time: u64,
+}
 
+/*
+amount of the grant vested at now, cliff and duration are in seconds
+*/
+pub fn vested_amount(grant: &VestingGrant, vesting: &VestingConfig, now: u64) -> u128 {
+    let elapsed = now.saturating_sub(grant.start_time) / 1000000000;
+    if elapsed < vesting.cliff {
+        0
+    } else if elapsed >= vesting.duration {
+        grant.amount
+    } else {
+        (U256::from(grant.amount) * U256::from(elapsed) / U256::from(vesting.duration)).as_u128()
+    }
+}
+
+/*
+bet volume of a player counted in a treasury epoch
+*/
+pub fn counted_volume(volume: u128, volume_cap: u128) -> u128 {
+    if volume_cap > 0 && volume > volume_cap {
+        volume_cap
+    } else {
+        volume
+    }
+}
+
+/*
+treasury reward of a stake not claimed yet, earned by its reward weight since it was last settled
+*/
+pub fn stake_treasury_reward(stake: &Stake, stake_reward_index: u128) -> u128 {
+    safe_add(stake.reward, safe_mul_div(stake.reward_weight, safe_sub(stake_reward_index, stake.reward_index), REWARD_INDEX_PRECISION))
+}
 
-#[near_bindgen]
 impl Contract {
-    pub fn treasury(&mut self) {
-        assert!(self.treasury_status.last_treasury_time < env::block_timestamp(), "too quick for treasury");
-        assert!(self.treasury_status.treasury_amount > self.config.treasury_threshold, "not enough treasury");
-        let player_amount = U256::from(self.treasury_status.treasury_amount) * U256::from(self.config.treasury_shares[0]) / U256::from(100 as u128);
-        let stakers_amount = U256::from(self.treasury_status.treasury_amount) * U256::from(self.config.treasury_shares[1]) / U256::from(100 as u128);
-        let team_amount = U256::from(self.treasury_status.treasury_amount) * U256::from(self.config.treasury_shares[2]) / U256::from(100 as u128);
-        let mut player_count = 0;
-        for (_, account) in self.accounts.iter() {
-            if u64::from(account.last_bet_time) > self.treasury_status.last_treasury_time {
-                player_count += 1
-            }
+    /*
+    stakers share the treasury by the weight of their stakes (value and seniority), settle what the stake earned
+    with its old weight and count the new one in the total, whenever the stake changes
+    */
+    pub(crate) fn internal_update_reward_weight(&mut self, stake: &mut Stake) {
+        stake.reward = stake_treasury_reward(stake, self.treasury_status.stake_reward_index);
+        stake.reward_index = self.treasury_status.stake_reward_index;
+        let weight = self.cal_stake_weight(stake).as_u128();
+        self.treasury_status.stake_weight = safe_add(safe_sub(self.treasury_status.stake_weight, stake.reward_weight), weight);
+        stake.reward_weight = weight;
+    }
+
+    /*
+    count the player's bet volume into the current treasury epoch when the bets are settled,
+    returns the reward of the previous epoch moved to balance
+    */
+    pub(crate) fn internal_record_player(&mut self, account_id: &AccountId, account: &mut Account, bet_amount: u128) -> u128 {
+        account.last_bet_time = env::block_timestamp();
+        let mut reward = 0;
+        if account.treasury_epoch != self.treasury_status.epoch_index {
+            reward = self.internal_claim_treasury(account_id, account, false);      // reward of the previous epoch, compounded after the round is settled
+            account.treasury_epoch = self.treasury_status.epoch_index;
+            account.treasury_volume = 0;
+            self.treasury_status.epoch_player_count += 1;
         }
-        let each_player_amount = player_amount.as_u128() / player_count;
+        let volume_cap = self.treasury_status.epoch_volume_cap;
+        let prev_volume = counted_volume(account.treasury_volume, volume_cap);
+        account.treasury_volume = safe_add(account.treasury_volume, bet_amount);
+        self.treasury_status.epoch_volume = safe_add(self.treasury_status.epoch_volume, safe_sub(counted_volume(account.treasury_volume, volume_cap), prev_volume));
+        reward
+    }
 
-        for (account_id, account) in (&mut self.accounts).to_vec().clone().iter_mut()   {
-            if account.last_bet_time > self.treasury_status.last_treasury_time {
-                account.balance += each_player_amount;
-            }
-            for stake in account.stakes.iter_mut() {
-                let (share_numerator, share_denominator) = self.cal_share(stake.clone());
-                let profit = (U256::from(stakers_amount) * share_numerator / share_denominator).as_u128();
-                stake.profit += profit;
+    pub(crate) fn cal_treasury_reward(&self, account: &Account) -> u128 {
+        let mut amount: u128 = 0;
+        if account.treasury_epoch > 0 && account.treasury_epoch < self.treasury_status.epoch_index {
+            if let Some(epoch) = self.treasury_epochs.get(&account.treasury_epoch) {
+                amount = safe_add(amount, safe_mul_div(epoch.player_amount, counted_volume(account.treasury_volume, epoch.volume_cap), epoch.volume));
             }
+        }
+        for stake in account.stakes.iter() {
+            amount = safe_add(amount, stake_treasury_reward(stake, self.treasury_status.stake_reward_index));
+        }
+        amount
+    }
+
+    /*
+    move the user's treasury reward to balance, or to a new stake if compound is allowed and auto compound is on
+    */
+    pub(crate) fn internal_claim_treasury(&mut self, account_id: &AccountId, account: &mut Account, compound: bool) -> u128 {
+        let amount = self.cal_treasury_reward(account);
+        if account.treasury_epoch < self.treasury_status.epoch_index {
+            account.treasury_epoch = 0;
+            account.treasury_volume = 0;
+        }
+        for stake in account.stakes.iter_mut() {
+            stake.reward_index = self.treasury_status.stake_reward_index;
+            stake.reward = 0;
+        }
+        self.treasury_status.unclaimed_amount = safe_sub(self.treasury_status.unclaimed_amount, amount);
+        if compound && account.auto_compound && amount > 0 {
+            self.internal_stake(account_id, account, amount);
+        } else {
+            account.balance = safe_add(account.balance, amount);
+        }
+        amount
+    }
+
+    /*
+    stake the rewards claimed while the round was settled, after the profit of the round is shared by the current stakes
+    */
+    pub(crate) fn internal_compound_rewards(&mut self, rewards: Vec<(AccountId, u128)>) {
+        for (account_id, amount) in rewards {
+            let mut account = self.accounts.get(&account_id).unwrap();
+            account.balance = safe_sub(account.balance, amount);
+            self.internal_stake(&account_id, &mut account, amount);
             self.accounts.insert(&account_id, &account);
         }
-        Promise::new(self.owner_id.clone()).transfer(team_amount.as_u128());
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+    close the current epoch and split the treasury to the beneficiaries, players and stake users claim their rewards by themselves.
+    a share without anyone to take it stays in the treasury
+    */
+    pub fn treasury(&mut self) {
+        require(self.treasury_status.last_treasury_time < env::block_timestamp(), RoulefiError::TooQuickForTreasury);
+        require(self.treasury_status.treasury_amount > self.config.treasury_threshold, RoulefiError::NotEnoughTreasury);
+        let treasury_amount = self.treasury_status.treasury_amount;
+        let mut distributed_amount: u128 = 0;
+        for share in self.config.treasury_shares.clone() {
+            let amount = safe_mul_div(treasury_amount, share.share_bps as u128, BPS_DENOMINATOR as u128);
+            match share.beneficiary {
+                Beneficiary::Players => {
+                    if self.treasury_status.epoch_volume > 0 {
+                        self.treasury_epochs.insert(&self.treasury_status.epoch_index, &TreasuryEpoch {
+                            player_amount: amount,
+                            volume: self.treasury_status.epoch_volume,
+                            volume_cap: self.treasury_status.epoch_volume_cap,
+                        });
+                        self.treasury_status.unclaimed_amount = safe_add(self.treasury_status.unclaimed_amount, amount);
+                        distributed_amount = safe_add(distributed_amount, amount);
+                    }
+                },
+                Beneficiary::Stakers => {
+                    if self.treasury_status.stake_weight > 0 {
+                        self.treasury_status.stake_reward_index = safe_add(self.treasury_status.stake_reward_index, safe_mul_div(amount, REWARD_INDEX_PRECISION, self.treasury_status.stake_weight));
+                        self.treasury_status.unclaimed_amount = safe_add(self.treasury_status.unclaimed_amount, amount);
+                        distributed_amount = safe_add(distributed_amount, amount);
+                    }
+                },
+                Beneficiary::Team => {
+                    self.team_vestings.push(&VestingGrant {
+                        amount,
+                        released: 0,
+                        start_time: env::block_timestamp(),
+                    });
+                    self.treasury_status.vesting_amount = safe_add(self.treasury_status.vesting_amount, amount);
+                    distributed_amount = safe_add(distributed_amount, amount);
+                },
+                Beneficiary::Insurance => {
+                    self.treasury_status.insurance_amount = safe_add(self.treasury_status.insurance_amount, amount);
+                    distributed_amount = safe_add(distributed_amount, amount);
+                },
+                Beneficiary::ReferralPool => {
+                    self.treasury_status.referral_pool_amount = safe_add(self.treasury_status.referral_pool_amount, amount);
+                    distributed_amount = safe_add(distributed_amount, amount);
+                },
+                Beneficiary::Account(account_id) => {
+                    let mut account = self.accounts.get(&account_id).unwrap_or(new_user());
+                    account.balance = safe_add(account.balance, amount);
+                    self.accounts.insert(&account_id, &account);
+                    distributed_amount = safe_add(distributed_amount, amount);
+                },
+            }
+        }
 
-        self.treasury_status.treasury_amount = 0;
+        self.treasury_status.treasury_amount = safe_sub(self.treasury_status.treasury_amount, distributed_amount);
+        self.treasury_status.epoch_index += 1;
+        self.treasury_status.epoch_player_count = 0;
+        self.treasury_status.epoch_volume = 0;
+        self.treasury_status.epoch_volume_cap = self.config.player_volume_cap;   // a new cap works from the next epoch
         self.treasury_status.last_treasury_time = env::block_timestamp();
+        self.assert_solvent();
     }
 
-    
+    /*
+    move the treasury reward of players and stakes to balance
+    */
+    pub fn claim_treasury(&mut self) -> U128 {
+        let sender_id = env::predecessor_account_id();
+        let mut account = self.accounts.get(&sender_id).or_fail(RoulefiError::AccountNotFound);
+        let amount = self.internal_claim_treasury(&sender_id, &mut account, true);
+        self.accounts.insert(&sender_id, &account);
+        self.assert_solvent();
+        U128::from(amount)
+    }
+
+    /*
+    transfer the vested team shares to owner
+    */
+    pub fn release_team_vesting(&mut self) -> U128 {
+        self.assert_owner();
+        let now = env::block_timestamp();
+        let mut amount: u128 = 0;
+        for i in 0..self.team_vestings.len() {
+            let mut grant = self.team_vestings.get(i).unwrap();
+            let releasable = safe_sub(vested_amount(&grant, &self.config.team_vesting, now), grant.released);
+            if releasable > 0 {
+                grant.released = safe_add(grant.released, releasable);
+                amount = safe_add(amount, releasable);
+                self.team_vestings.replace(i, &grant);
+            }
+        }
+        require(amount > 0, RoulefiError::NothingToRelease);
+        self.treasury_status.vesting_amount = safe_sub(self.treasury_status.vesting_amount, amount);
+        Promise::new(self.owner_id.clone()).transfer(amount);
+        self.assert_solvent();
+        U128::from(amount)
+    }
 }
 
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::testing_env;
+    use near_sdk::VMContext;
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice".to_string(),
+            signer_account_id: "bob".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "bob".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 1600000000000000000,
+            account_balance: CHIP_UNIT * 100000000,
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(15),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    #[test]
+    fn treasury_without_players() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+
+        let mut context = get_context();
+        context.attached_deposit = 300;
+        testing_env!(context);
+        contract.stake();
+
+        contract.treasury_status.treasury_amount = contract.config.treasury_threshold + 1000;
+        let treasury_amount = contract.treasury_status.treasury_amount;
+        contract.treasury();
+        let stakers_amount = treasury_amount * contract.config.treasury_shares[1].share_bps as u128 / 10000;
+        let team_amount = treasury_amount * contract.config.treasury_shares[2].share_bps as u128 / 10000;
+        assert_eq!(contract.treasury_status.vesting_amount, team_amount);
+        assert_eq!(contract.treasury_status.treasury_amount, treasury_amount - stakers_amount - team_amount);   // players' share stays
+        assert_eq!(contract.treasury_status.epoch_index, 2);
+
+        let claimed = contract.claim_treasury();
+        assert!(u128::from(claimed) <= stakers_amount && u128::from(claimed) > stakers_amount - 1000);
+        assert_eq!(contract.claim_treasury(), U128::from(0));
+    }
+
+    #[test]
+    fn auto_compound_treasury() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+
+        let mut context = get_context();
+        context.attached_deposit = 300;
+        testing_env!(context);
+        contract.stake();
+        contract.set_auto_compound(true);
+
+        contract.treasury_status.treasury_amount = contract.config.treasury_threshold + 1000;
+        contract.treasury();
+        let stake_amount = contract.round_status.stake_amount;
+        let claimed = u128::from(contract.claim_treasury());
+        let account = contract.accounts.get(&"bob".to_string()).unwrap();
+        assert_eq!(account.balance, 0);
+        assert_eq!(account.stakes.len(), 2);
+        assert_eq!(account.stakes[1].amount, claimed);
+        assert_eq!(contract.round_status.stake_amount, stake_amount + claimed);
+    }
+
+    #[test]
+    fn stake_reward_by_weight() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+
+        let mut context = get_context();
+        context.attached_deposit = 100000;
+        testing_env!(context);
+        contract.stake();
+
+        let mut context = get_context();
+        context.predecessor_account_id = "carol".to_string();
+        context.block_timestamp += DAY_NANOS * 8;      // bob's stake is a week old
+        context.attached_deposit = 100000;
+        testing_env!(context);
+        contract.stake();
+        contract.distribute_pool(1000, true);           // a round result updates the weight of every stake
+
+        let bob = contract.accounts.get(&"bob".to_string()).unwrap();
+        let carol = contract.accounts.get(&"carol".to_string()).unwrap();
+        let bob_weight = contract.cal_stake_weight(&bob.stakes[0]).as_u128();
+        let carol_weight = contract.cal_stake_weight(&carol.stakes[0]).as_u128();
+        assert!(bob_weight > carol_weight);
+        assert_eq!(contract.treasury_status.stake_weight, bob_weight + carol_weight);
+
+        contract.treasury_status.treasury_amount = contract.config.treasury_threshold + 1000;
+        let stakers_amount = contract.treasury_status.treasury_amount * contract.config.treasury_shares[1].share_bps as u128 / 10000;
+        contract.treasury();
+        let bob_reward = contract.cal_treasury_reward(&bob);
+        let carol_reward = contract.cal_treasury_reward(&carol);
+        assert!(bob_reward > carol_reward);
+        assert!(stakers_amount * bob_weight / (bob_weight + carol_weight) - bob_reward <= 1);
+        assert!(stakers_amount * carol_weight / (bob_weight + carol_weight) - carol_reward <= 1);
+    }
+
+    #[test]
+    fn treasury_by_volume() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.treasury_status.epoch_volume_cap = 500;
+
+        let mut bob = new_user();
+        let mut carol = new_user();
+        let mut dave = new_user();
+        let bob_id = "bob".to_string();
+        let carol_id = "carol".to_string();
+        let dave_id = "dave".to_string();
+        contract.internal_record_player(&bob_id, &mut bob, 100);
+        contract.internal_record_player(&carol_id, &mut carol, 200);
+        contract.internal_record_player(&carol_id, &mut carol, 100);
+        contract.internal_record_player(&dave_id, &mut dave, 1000);    // counted as 500
+        assert_eq!(contract.treasury_status.epoch_player_count, 3);
+        assert_eq!(contract.treasury_status.epoch_volume, 900);
+
+        contract.treasury_status.treasury_amount = contract.config.treasury_threshold + 1;
+        let player_amount = contract.treasury_status.treasury_amount * contract.config.treasury_shares[0].share_bps as u128 / 10000;
+        contract.treasury();
+        assert_eq!(contract.cal_treasury_reward(&bob), player_amount * 100 / 900);
+        assert_eq!(contract.cal_treasury_reward(&carol), player_amount * 300 / 900);
+        assert_eq!(contract.cal_treasury_reward(&dave), player_amount * 500 / 900);
+
+        contract.internal_record_player(&bob_id, &mut bob, 100);    // the reward of last epoch is claimed when bob bets again
+        assert_eq!(bob.balance, player_amount * 100 / 900);
+        assert_eq!(bob.treasury_epoch, 2);
+        assert_eq!(contract.cal_treasury_reward(&bob), 0);
+    }
+
+    #[test]
+    fn team_vesting_with_cliff() {
+        let vesting = VestingConfig {
+            cliff: 100,
+            duration: 1000,
+        };
+        let grant = VestingGrant {
+            amount: 5000,
+            released: 0,
+            start_time: 1600000000000000000,
+        };
+        let second = 1000000000;
+        assert_eq!(vested_amount(&grant, &vesting, grant.start_time), 0);
+        assert_eq!(vested_amount(&grant, &vesting, grant.start_time + 99 * second), 0);
+        assert_eq!(vested_amount(&grant, &vesting, grant.start_time + 100 * second), 500);
+        assert_eq!(vested_amount(&grant, &vesting, grant.start_time + 500 * second), 2500);
+        assert_eq!(vested_amount(&grant, &vesting, grant.start_time + 1000 * second), 5000);
+        assert_eq!(vested_amount(&grant, &vesting, grant.start_time + 5000 * second), 5000);
+    }
+
+    #[test]
+    fn treasury_to_named_beneficiaries() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = Contract::new();
+        contract.set_treasury_shares(vec![
+            TreasuryShare { beneficiary: Beneficiary::Insurance, share_bps: 5000 },
+            TreasuryShare { beneficiary: Beneficiary::ReferralPool, share_bps: 3000 },
+            TreasuryShare { beneficiary: Beneficiary::Account("carol".to_string()), share_bps: 2000 },
+        ]);
+        contract.treasury_status.treasury_amount = contract.config.treasury_threshold + 10000;
+        let treasury_amount = contract.treasury_status.treasury_amount;
+        contract.treasury();
+        assert_eq!(contract.treasury_status.insurance_amount, treasury_amount / 2);
+        assert_eq!(contract.treasury_status.referral_pool_amount, treasury_amount * 3 / 10);
+        assert_eq!(contract.accounts.get(&"carol".to_string()).unwrap().balance, treasury_amount / 5);
+        assert_eq!(contract.treasury_status.treasury_amount, 0);
+    }
+}
@@ -1,4 +1,11 @@
 use crate::*;
+use near_sdk::json_types::{Base64VecU8, I128};
+use crate::limits::LimitState;
+use crate::strategy::Progression;
+use crate::tournament::entry_chips;
+use crate::treasury::stake_treasury_reward;
+use crate::stats::LeaderboardKind;
+use crate::wheel::{distribution, color_streaks};
 
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -28,7 +35,13 @@ To show the basic user info and contract info
 pub struct AccountStatusInfo {
     bets: Vec<BetInfo>,
     balance: U128,
-    stakes: Vec<StakeInfo>                 
+    stakes: Vec<StakeInfo>,
+    treasury_volume: U128,      // bet volume counted in current treasury epoch
+    auto_compound: bool,
+    jackpot_chips: U128,        // jackpot side bet of current round
+    straight_streak: u32,       // rounds in a row the straight bets hit
+    tournament_id: Option<U64>, // the tournament being played
+    tournament_chips: U128,     // including the ones on the table
 }
 
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
@@ -38,10 +51,16 @@ pub struct RoundStatusInfo {
     current_round_block_index: U64,
     round_index: U64,
     next_round_block_index: U64,
+    bet_close_block_index: U64,  // no more bets after this block
+    refund_block_index: U64,    // bets can be refunded after this block if the wheel is not spun
     last_round_win_number: u8,
     spinning: bool,       
+    state: RoundState,
     bet_amount: U128,           // total bet amount in this round
+    table_bet_amount: U128,     // open bets of shared private tables
     bet_count: u32,
+    gas_per_player: U128,       // keeper fee charged from every player when the wheel spins
+    keeper_reward: U128,        // reward for the caller of spin_wheel
 }
 
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
@@ -55,6 +74,315 @@ pub struct ContractStatusInfo {
     loss_amount: U128       
 }
 
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct LimitInfo {
+    limits: Vec<U128>,          // daily, weekly and monthly caps, 0 for no limit
+    used: Vec<U128>,            // used in the current day, week and month
+    pending_limits: Vec<U128>,  // caps after pending_time
+    pending_time: U64,          // 0 if nothing is pending
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct GamingLimitsInfo {
+    deposit: LimitInfo,
+    loss: LimitInfo,
+    wager: LimitInfo,
+    exclusion_end: U64,
+}
+
+fn limit_info(state: &LimitState) -> LimitInfo {
+    LimitInfo {
+        limits: vec![U128::from(state.limits.daily), U128::from(state.limits.weekly), U128::from(state.limits.monthly)],
+        used: vec![U128::from(state.usage.daily), U128::from(state.usage.weekly), U128::from(state.usage.monthly)],
+        pending_limits: vec![U128::from(state.pending_limits.daily), U128::from(state.pending_limits.weekly), U128::from(state.pending_limits.monthly)],
+        pending_time: U64::from(state.pending_time),
+    }
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct ReferralStatsInfo {
+    referrer_id: Option<AccountId>,     // who referred the account
+    referral_count: U64,        // players referred by the account
+    earned_amount: U128,        // commissions earned from all tiers
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct ReferralPolicyInfo {
+    tier_bps: Vec<u32>,         // share of house profit for each tier, in basis points
+    max_tiers: u32,
+    referral_pool_amount: U128, // commissions are paid from the pool first
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct JackpotStatusInfo {
+    pot_amount: U128,
+    jackpot_chip: U128,         // price of a side bet, 0 when the jackpot is closed
+    payout_bps: u32,            // part of the pot paid when it is hit
+    streak: u32,                // straight bets hitting this many rounds in a row hit the jackpot
+    last_number: Option<u8>,    // a straight bet hitting this number again hits the jackpot
+    last_payout: U128,
+    last_winners: Vec<AccountId>,
+    last_payout_round: U64,
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct StandingOrderInfo {
+    bets: Vec<BetInfo>,
+    rounds_left: Option<U64>,   // None until cancelled
+    placed_rounds: U64,
+    skipped_rounds: U64,
+    last_error: Option<u32>,    // error code of the last skipped round
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct StrategyInfo {
+    progression: Progression,
+    base_chips: U128,
+    stop_loss: U128,            // 0 for no stop
+    take_profit: U128,          // 0 for no stop
+    max_steps: u32,
+    step: u32,
+    next_chips: U128,           // chips of every bet in the next placed round
+    bet_amount: U128,
+    won_amount: U128,
+    active: bool,               // false after stop loss or take profit
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct TournamentInfo {
+    table_id: U64,              // the table the tournament is played on
+    entry_fee: U128,
+    starting_chips: U128,
+    start_time: U64,
+    end_time: U64,
+    payout_bps: Vec<u32>,       // prize pool shares by rank
+    max_players: u32,
+    player_count: u32,
+    prize_pool: U128,
+    finished: bool,
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct TournamentRankInfo {
+    account_id: AccountId,
+    chips: U128,
+    prize: U128,                // paid when the tournament finished, 0 before
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct PlayerStatsInfo {
+    rounds_played: U64,
+    total_wagered: U128,
+    total_won: U128,            // paid back by winning bets, chips included
+    biggest_win: U128,          // largest net win of a round
+    net_winnings: I128,         // total_won - total_wagered
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct LeaderboardEntryInfo {
+    account_id: AccountId,
+    rounds: U64,
+    wagered: U128,
+    won: U128,
+    net_winnings: I128,
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct PrivateTableInfo {
+    host_id: AccountId,
+    allowlist: Vec<AccountId>,
+    min_chips: Vec<U128>,       // indexed by bet_type
+    max_chips: Vec<U128>,       // indexed by bet_type
+    round_delta: U64,
+    host_bps: u32,              // host's part of the table's house profit
+    shared_bankroll: bool,      // plays against the staking pool
+    bankroll_amount: U128,      // separate bankroll
+    round_index: U64,
+    next_round_block_index: U64,
+    round_state: RoundState,
+    bets: Vec<(AccountId, Vec<BetInfo>)>,
+    bet_amount: U128,
+    last_number: Option<u8>,
+    closed: bool,
+    tournament_id: Option<U64>, // bets are tournament chips of this tournament
+}
+
+/*
+colors are 0 for black, 1 for red and 2 for green as in the color bets
+*/
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct WheelStatsInfo {
+    spins: U64,
+    counts: Vec<U64>,           // by pocket
+    colors: Vec<U64>,           // black, red, green
+    dozens: Vec<U64>,
+    columns: Vec<U64>,
+    odd_even: Vec<U64>,         // even, odd
+    low_high: Vec<U64>,         // 1-18, 19-36
+    hot_numbers: Vec<u8>,       // most frequent first
+    cold_numbers: Vec<u8>,      // least frequent first
+    current_streak: (u8, u32),  // (color, length) of the current run, 0 breaks every run
+    longest_streak: (u8, u32),  // longest run of the recent spins
+    chi_square_milli: U64,      // chi-square statistic versus a uniform wheel times 1000, 36 degrees of freedom
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct TableLimitsInfo {
+    min_chips: Vec<U128>,       // indexed by bet_type
+    max_chips: Vec<U128>,       // indexed by bet_type
+    chip_unit: U128,
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct TreasuryStatusInfo {
+    last_treasury_time: U64,
+    treasury_amount: U128,      // waiting for next distribution
+    threshold: U128,            // treasury can be distributed when treasury_amount exceeds threshold
+    epoch_index: U64,
+    epoch_player_count: U64,    // players who share the next distribution
+    epoch_volume: U128,         // players share the next distribution in proportion to their bet volume
+    player_volume_cap: U128,    // max bet volume of a player counted in this epoch, 0 for no cap
+    unclaimed_amount: U128,     // distributed but not claimed yet
+    insurance_amount: U128,
+    referral_pool_amount: U128,
+    shares: Vec<TreasuryShare>,
+}
+
+/*
+realized pool yield of the last days, from the pool profit and loss after treasury and insurance
+*/
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct PoolPerformanceInfo {
+    days: u64,
+    profit: U128,
+    loss: U128,
+    average_pool_amount: U128,
+    yield_bps: i64,             // (profit - loss) / average_pool_amount, in basis points
+    apy_bps: i64,               // yield_bps for a year
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct StakeValueInfo {
+    amount: U128,
+    value: U128,                // amount + profit - loss
+    time_rate: u32,             // share multiplier in percentage
+    share_bps: u32,             // share of the pool profit and loss, in basis points
+    unclaimed_treasury: U128,   // treasury reward to claim
+    projected_treasury: U128,   // share of the next treasury if it is distributed now
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct SeniorityScheduleInfo {
+    step_time: Vec<U64>,        // in seconds
+    step_rate: Vec<u32>,        // share bonus in percentage
+    curve: SeniorityCurve,
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct DelegationStatusInfo {
+    validator_id: Option<AccountId>,
+    validators: Vec<AccountId>,  // whitelisted validator pools
+    delegate_rate: u32,
+    staked_amount: U128,
+    unstaked_amount: U128,
+    unstake_epoch: U64,
+    delegable_amount: U128,     // can be delegated now
+    pending_amount: U128,       // sent to the validator pool and not confirmed yet
+    pending: bool,
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct InsuranceStatusInfo {
+    insurance_amount: U128,     // insurance fund balance
+    insurance_rate: u32,        // percentage of round profit after treasury
+    insurance_cap: U128,        // the fund is topped up to insurance_cap
+    pool_deficit: U128,         // loss of the pool not covered yet
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct ReservesInfo {
+    balance_amount: U128,       // players' balance
+    bet_amount: U128,           // chips of open bets
+    pool_amount: U128,          // stake values
+    treasury_amount: U128,      // treasury, unclaimed rewards, referral pool and vesting
+    insurance_amount: U128,
+    jackpot_amount: U128,       // jackpot pot
+    tournament_amount: U128,    // prize pools of tournaments not finished
+    table_amount: U128,         // separate bankrolls and open bets of private tables
+    storage_amount: U128,       // locked for contract storage
+    liabilities: U128,
+    contract_balance: U128,
+    delegated_amount: U128,     // staked, unstaked and in flight to the validator pool
+    assets: U128,
+    surplus: U128,              // assets above liabilities, 0 when insolvent
+    solvent: bool,
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct TeamVestingInfo {
+    cliff: U64,                 // in seconds
+    duration: U64,              // in seconds
+    total_amount: U128,
+    released_amount: U128,
+    releasable_amount: U128,    // vested but not released
+}
+
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+#[derive(Debug)]
+pub struct TreasuryDropInfo {
+    epoch_index: U64,
+    root: Base64VecU8,
+    amount: U128,
+    claimed_amount: U128,
+    expire_time: U64,
+    expired: bool,
+}
+
 #[near_bindgen]
 impl Contract {
     pub fn get_account_status(&self, account_id: AccountId) -> AccountStatusInfo {
@@ -62,7 +390,13 @@ impl Contract {
         let mut status = AccountStatusInfo {     // when can we play again
             bets: Vec::new(),
             stakes: Vec::new(),
-            balance: U128::from(account.balance)
+            balance: U128::from(account.balance),
+            treasury_volume: U128::from(if account.treasury_epoch == self.treasury_status.epoch_index { account.treasury_volume } else { 0 }),
+            auto_compound: account.auto_compound,
+            jackpot_chips: U128::from(account.jackpot_chips),
+            straight_streak: if account.straight_round + 1 == self.round_status.round_index { account.straight_streak } else { 0 },    // a missed round breaks the streak
+            tournament_id: account.tournament.as_ref().map(|entry| U64::from(entry.tournament_id)),
+            tournament_chips: U128::from(entry_chips(&account)),
         };
         for bet in account.bets {
             status.bets.push(BetInfo {
@@ -97,11 +431,406 @@ impl Contract {
         RoundStatusInfo {
             current_round_block_index: U64::from(self.round_status.current_round_block_index),
             round_index: U64::from(self.round_status.round_index),
-            next_round_block_index: U64::from(self.round_status.current_round_block_index + self.config.round_delta),
+            next_round_block_index: U64::from(self.round_status.next_round_block_index),
+            bet_close_block_index: U64::from(self.bet_close_block_index()),
+            refund_block_index: U64::from(self.round_status.next_round_block_index + self.config.refund_delay),
             bet_amount: U128::from(self.round_status.bet_amount),
+            table_bet_amount: U128::from(self.round_status.table_bet_amount),
             bet_count: self.bet_accounts.len() as u32,
+            gas_per_player: U128::from(self.config.gas_per_player),
+            keeper_reward: U128::from(self.config.gas_per_player * self.bet_accounts.len() as u128),
             spinning: self.round_status.spinning,
+            state: self.round_state(),
             last_round_win_number: self.round_status.last_round_win_number,
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_treasury_status(&self) -> TreasuryStatusInfo {
+        TreasuryStatusInfo {
+            last_treasury_time: U64::from(self.treasury_status.last_treasury_time),
+            treasury_amount: U128::from(self.treasury_status.treasury_amount),
+            threshold: U128::from(self.config.treasury_threshold),
+            epoch_index: U64::from(self.treasury_status.epoch_index),
+            epoch_player_count: U64::from(self.treasury_status.epoch_player_count),
+            epoch_volume: U128::from(self.treasury_status.epoch_volume),
+            player_volume_cap: U128::from(self.treasury_status.epoch_volume_cap),
+            unclaimed_amount: U128::from(self.treasury_status.unclaimed_amount),
+            insurance_amount: U128::from(self.treasury_status.insurance_amount),
+            referral_pool_amount: U128::from(self.treasury_status.referral_pool_amount),
+            shares: self.config.treasury_shares.clone(),
+        }
+    }
+
+    pub fn get_pool_performance(&self, days: u64) -> PoolPerformanceInfo {
+        require(days > 0 && days <= 365, RoulefiError::InvalidDays);
+        let today = env::block_timestamp() / DAY_NANOS;
+        let mut profit: u128 = 0;
+        let mut loss: u128 = 0;
+        let mut pool_total: u128 = 0;
+        let mut pool_days: u128 = 0;
+        for day in (today + 1).saturating_sub(days)..=today {
+            if let Some(pool_day) = self.pool_history.get(&day) {
+                profit = safe_add(profit, pool_day.profit);
+                loss = safe_add(loss, pool_day.loss);
+                pool_total = safe_add(pool_total, pool_day.pool_amount);
+                pool_days += 1;
+            }
+        }
+        let average_pool_amount = if pool_days > 0 { pool_total / pool_days } else { self.pool_amount() };
+        let yield_bps = if average_pool_amount > 0 {
+            ((profit as i128 - loss as i128) * BPS_DENOMINATOR as i128 / average_pool_amount as i128) as i64
+        } else {
+            0
+        };
+        PoolPerformanceInfo {
+            days,
+            profit: U128::from(profit),
+            loss: U128::from(loss),
+            average_pool_amount: U128::from(average_pool_amount),
+            yield_bps,
+            apy_bps: yield_bps * 365 / days as i64,
+        }
+    }
+
+    /*
+    pool performance of last day, week and month
+    */
+    pub fn get_pool_yields(&self) -> Vec<PoolPerformanceInfo> {
+        vec![1, 7, 30].into_iter().map(|days| self.get_pool_performance(days)).collect()
+    }
+
+    pub fn get_stake_values(&self, account_id: AccountId) -> Vec<StakeValueInfo> {
+        let account = self.accounts.get(&account_id).unwrap_or(new_user());
+        let stakers_bps: u32 = self.config.treasury_shares.iter()
+            .filter(|share| share.beneficiary == Beneficiary::Stakers)
+            .map(|share| share.share_bps)
+            .sum();
+        let stakers_amount = U256::from(self.treasury_status.treasury_amount) * U256::from(stakers_bps) / U256::from(BPS_DENOMINATOR);
+        let total_weight = self.cal_total_weight();
+        account.stakes.iter().map(|stake| {
+            let (share_numerator, share_denominator) = self.cal_share(stake, total_weight);
+            let share_bps = if share_denominator.is_zero() { 0 } else { (share_numerator * U256::from(BPS_DENOMINATOR) / share_denominator).as_u32() };
+            let unclaimed_treasury = stake_treasury_reward(stake, self.treasury_status.stake_reward_index);
+            let projected_treasury = if share_denominator.is_zero() { U256::zero() } else { stakers_amount * share_numerator / share_denominator };
+            StakeValueInfo {
+                amount: U128::from(stake.amount),
+                value: U128::from(safe_sub(safe_add(stake.amount, stake.profit), stake.loss)),
+                time_rate: self.cal_time_rate(stake),
+                share_bps,
+                unclaimed_treasury: U128::from(unclaimed_treasury),
+                projected_treasury: U128::from(projected_treasury.as_u128()),
+            }
+        }).collect()
+    }
+
+    pub fn get_seniority_schedule(&self) -> SeniorityScheduleInfo {
+        SeniorityScheduleInfo {
+            step_time: self.config.step_time.iter().map(|time| U64::from(*time)).collect(),
+            step_rate: self.config.step_rate.clone(),
+            curve: self.config.seniority_curve.clone(),
+        }
+    }
+
+    /*
+    the share multiplier in percentage for a stake staked for staked_seconds
+    */
+    pub fn get_seniority_rate(&self, staked_seconds: U64) -> u32 {
+        crate::internal::seniority_rate(&self.config.step_time, &self.config.step_rate, &self.config.seniority_curve, staked_seconds.into())
+    }
+
+    pub fn get_delegation_status(&self) -> DelegationStatusInfo {
+        DelegationStatusInfo {
+            validator_id: self.delegation.validator_id.clone(),
+            validators: self.config.validators.clone(),
+            delegate_rate: self.config.delegate_rate,
+            staked_amount: U128::from(self.delegation.staked_amount),
+            unstaked_amount: U128::from(self.delegation.unstaked_amount),
+            unstake_epoch: U64::from(self.delegation.unstake_epoch),
+            delegable_amount: U128::from(self.cal_delegable_amount()),
+            pending_amount: U128::from(self.delegation.pending_amount),
+            pending: self.delegation.pending,
+        }
+    }
+
+    pub fn get_insurance_status(&self) -> InsuranceStatusInfo {
+        InsuranceStatusInfo {
+            insurance_amount: U128::from(self.treasury_status.insurance_amount),
+            insurance_rate: self.config.insurance_rate,
+            insurance_cap: U128::from(self.config.insurance_cap),
+            pool_deficit: U128::from(self.round_status.loss_amount),
+        }
+    }
+
+    pub fn get_team_vesting(&self) -> TeamVestingInfo {
+        let now = env::block_timestamp();
+        let mut total_amount: u128 = 0;
+        let mut released_amount: u128 = 0;
+        let mut releasable_amount: u128 = 0;
+        for grant in self.team_vestings.iter() {
+            total_amount = safe_add(total_amount, grant.amount);
+            released_amount = safe_add(released_amount, grant.released);
+            releasable_amount = safe_add(releasable_amount, safe_sub(crate::treasury::vested_amount(&grant, &self.config.team_vesting, now), grant.released));
+        }
+        TeamVestingInfo {
+            cliff: U64::from(self.config.team_vesting.cliff),
+            duration: U64::from(self.config.team_vesting.duration),
+            total_amount: U128::from(total_amount),
+            released_amount: U128::from(released_amount),
+            releasable_amount: U128::from(releasable_amount),
+        }
+    }
+
+    /*
+    treasury reward the user can claim now
+    */
+    pub fn get_claimable_treasury(&self, account_id: AccountId) -> U128 {
+        let account = self.accounts.get(&account_id).unwrap_or(new_user());
+        U128::from(self.cal_treasury_reward(&account))
+    }
+
+    pub fn get_treasury_drop(&self, drop_id: U64) -> Option<TreasuryDropInfo> {
+        self.treasury_drops.get(u64::from(drop_id)).map(|drop| TreasuryDropInfo {
+            epoch_index: U64::from(drop.epoch_index),
+            root: Base64VecU8::from(drop.root),
+            amount: U128::from(drop.amount),
+            claimed_amount: U128::from(drop.claimed_amount),
+            expire_time: U64::from(drop.expire_time),
+            expired: drop.expired,
+        })
+    }
+
+    pub fn is_drop_claimed(&self, drop_id: U64, account_id: AccountId) -> bool {
+        self.drop_claims.contains(&crate::airdrop::drop_claim_key(u64::from(drop_id), &account_id))
+    }
+
+    /*
+    liabilities against assets of the contract, it walks all accounts
+    */
+    pub fn get_reserves(&self) -> ReservesInfo {
+        let reserves = self.cal_reserves();
+        let (liabilities, assets) = (reserves.liabilities(), reserves.assets());
+        ReservesInfo {
+            balance_amount: U128::from(reserves.balance_amount),
+            bet_amount: U128::from(reserves.bet_amount),
+            pool_amount: U128::from(reserves.pool_amount),
+            treasury_amount: U128::from(reserves.treasury_amount),
+            insurance_amount: U128::from(reserves.insurance_amount),
+            jackpot_amount: U128::from(reserves.jackpot_amount),
+            tournament_amount: U128::from(reserves.tournament_amount),
+            table_amount: U128::from(reserves.table_amount),
+            storage_amount: U128::from(reserves.storage_amount),
+            liabilities: U128::from(liabilities),
+            contract_balance: U128::from(reserves.contract_balance),
+            delegated_amount: U128::from(reserves.delegated_amount),
+            assets: U128::from(assets),
+            surplus: U128::from(assets.saturating_sub(liabilities)),
+            solvent: assets >= liabilities,
+        }
+    }
+
+    /*
+    gaming limits of the account as they work now, None if the account never set any
+    */
+    pub fn get_gaming_limits(&self, account_id: AccountId) -> Option<GamingLimitsInfo> {
+        self.gaming_limits.get(&account_id).map(|mut limits| {
+            limits.refresh(env::block_timestamp());
+            GamingLimitsInfo {
+                deposit: limit_info(&limits.deposit),
+                loss: limit_info(&limits.loss),
+                wager: limit_info(&limits.wager),
+                exclusion_end: U64::from(limits.exclusion_end),
+            }
+        })
+    }
+
+    pub fn get_referral_stats(&self, account_id: AccountId) -> ReferralStatsInfo {
+        let stats = self.referral_stats.get(&account_id).unwrap_or_default();
+        ReferralStatsInfo {
+            referrer_id: self.referrers.get(&account_id),
+            referral_count: U64::from(stats.referral_count),
+            earned_amount: U128::from(stats.earned_amount),
+        }
+    }
+
+    pub fn get_referral_policy(&self) -> ReferralPolicyInfo {
+        ReferralPolicyInfo {
+            tier_bps: self.config.referral_tier_bps.clone(),
+            max_tiers: self.config.referral_max_tiers,
+            referral_pool_amount: U128::from(self.treasury_status.referral_pool_amount),
+        }
+    }
+
+    pub fn get_jackpot_status(&self) -> JackpotStatusInfo {
+        JackpotStatusInfo {
+            pot_amount: U128::from(self.jackpot.pot_amount),
+            jackpot_chip: U128::from(self.config.jackpot_chip),
+            payout_bps: self.config.jackpot_payout_bps,
+            streak: self.config.jackpot_streak,
+            last_number: self.jackpot.last_number,
+            last_payout: U128::from(self.jackpot.last_payout),
+            last_winners: self.jackpot.last_winners.clone(),
+            last_payout_round: U64::from(self.jackpot.last_payout_round),
+        }
+    }
+
+    pub fn get_standing_order(&self, account_id: AccountId) -> Option<StandingOrderInfo> {
+        self.standing_orders.get(&account_id).map(|order| StandingOrderInfo {
+            bets: order.bets.iter().map(|bet| BetInfo {
+                bet_type: bet.bet_type,
+                number: bet.number,
+                chips: U128::from(bet.chips),
+            }).collect(),
+            rounds_left: order.rounds_left.map(|rounds| U64::from(rounds)),
+            placed_rounds: U64::from(order.placed_rounds),
+            skipped_rounds: U64::from(order.skipped_rounds),
+            last_error: order.last_error,
+        })
+    }
+
+    pub fn get_strategy(&self, account_id: AccountId) -> Option<StrategyInfo> {
+        self.strategies.get(&account_id).map(|strategy| StrategyInfo {
+            progression: strategy.progression,
+            base_chips: U128::from(strategy.base_chips),
+            stop_loss: U128::from(strategy.stop_loss),
+            take_profit: U128::from(strategy.take_profit),
+            max_steps: strategy.max_steps,
+            step: strategy.step,
+            next_chips: U128::from(strategy.next_chips()),
+            bet_amount: U128::from(strategy.bet_amount),
+            won_amount: U128::from(strategy.won_amount),
+            active: strategy.active,
+        })
+    }
+
+    pub fn get_tournament(&self, tournament_id: U64) -> Option<TournamentInfo> {
+        self.tournaments.get(u64::from(tournament_id)).map(|tournament| TournamentInfo {
+            table_id: U64::from(tournament.table_id),
+            entry_fee: U128::from(tournament.entry_fee),
+            starting_chips: U128::from(tournament.starting_chips),
+            start_time: U64::from(tournament.start_time),
+            end_time: U64::from(tournament.end_time),
+            payout_bps: tournament.payout_bps,
+            max_players: tournament.max_players,
+            player_count: tournament.players.len() as u32,
+            prize_pool: U128::from(tournament.prize_pool),
+            finished: tournament.finished,
+        })
+    }
+
+    /*
+    live ranking by chips, the final one once the tournament finished
+    */
+    pub fn get_tournament_leaderboard(&self, tournament_id: U64) -> Vec<TournamentRankInfo> {
+        let tournament = match self.tournaments.get(u64::from(tournament_id)) {
+            Some(tournament) => tournament,
+            None => return Vec::new(),
+        };
+        if tournament.finished {
+            return tournament.results.iter().map(|result| TournamentRankInfo {
+                account_id: result.account_id.clone(),
+                chips: U128::from(result.chips),
+                prize: U128::from(result.prize),
+            }).collect();
+        }
+        self.tournament_standings(&tournament).into_iter().map(|(account_id, chips)| TournamentRankInfo {
+            account_id,
+            chips: U128::from(chips),
+            prize: U128::from(0),
+        }).collect()
+    }
+
+    pub fn get_player_stats(&self, account_id: AccountId) -> Option<PlayerStatsInfo> {
+        self.player_stats.get(&account_id).map(|stats| PlayerStatsInfo {
+            rounds_played: U64::from(stats.rounds_played),
+            total_wagered: U128::from(stats.total_wagered),
+            total_won: U128::from(stats.total_won),
+            biggest_win: U128::from(stats.biggest_win),
+            net_winnings: I128::from(stats.total_won as i128 - stats.total_wagered as i128),
+        })
+    }
+
+    /*
+    top players by net winnings or volume, all time without window_days, else over the last window_days days (30 at most)
+    */
+    pub fn get_leaderboard(&self, kind: LeaderboardKind, window_days: Option<U64>, limit: u32) -> Vec<LeaderboardEntryInfo> {
+        self.cal_leaderboard(kind, window_days.map(|days| u64::from(days)), limit).into_iter().map(|(account_id, stats)| LeaderboardEntryInfo {
+            account_id,
+            rounds: U64::from(stats.rounds),
+            wagered: U128::from(stats.wagered),
+            won: U128::from(stats.won),
+            net_winnings: I128::from(stats.net()),
+        }).collect()
+    }
+
+    /*
+    winning numbers of the last rounds, newest first, of the main wheel or of a private table
+    */
+    pub fn get_recent_numbers(&self, limit: u32, table_id: Option<U64>) -> Vec<u8> {
+        let wheel = self.wheel_stats_of(table_id.map(|table_id| u64::from(table_id)));
+        wheel.recent.iter().rev().take(limit as usize).cloned().collect()
+    }
+
+    /*
+    distribution of the recent spins or of all time, streaks are always taken from the recent spins.
+    a private table's spins are counted apart from the main wheel
+    */
+    pub fn get_wheel_stats(&self, all_time: bool, top: u32, table_id: Option<U64>) -> WheelStatsInfo {
+        let wheel = self.wheel_stats_of(table_id.map(|table_id| u64::from(table_id)));
+        let counts = if all_time { wheel.counts.clone() } else { wheel.recent_counts() };
+        let stats = distribution(&counts, top as usize);
+        let (current_streak, longest_streak) = color_streaks(&wheel.recent);
+        let to_json = |counts: &[u64]| -> Vec<U64> { counts.iter().map(|count| U64::from(*count)).collect() };
+        WheelStatsInfo {
+            spins: U64::from(stats.spins),
+            counts: to_json(&stats.counts[..]),
+            colors: to_json(&stats.colors[..]),
+            dozens: to_json(&stats.dozens[..]),
+            columns: to_json(&stats.columns[..]),
+            odd_even: to_json(&stats.odd_even[..]),
+            low_high: to_json(&stats.low_high[..]),
+            hot_numbers: stats.hot_numbers,
+            cold_numbers: stats.cold_numbers,
+            current_streak,
+            longest_streak,
+            chi_square_milli: U64::from(stats.chi_square_milli),
+        }
+    }
+
+    pub fn get_private_table(&self, table_id: U64) -> Option<PrivateTableInfo> {
+        self.tables.get(u64::from(table_id)).map(|table| PrivateTableInfo {
+            round_state: table.round_state(self.config.bet_close_delta),
+            host_id: table.host_id,
+            allowlist: table.allowlist,
+            min_chips: table.limits.min_chips.iter().map(|chips| U128::from(*chips)).collect(),
+            max_chips: table.limits.max_chips.iter().map(|chips| U128::from(*chips)).collect(),
+            round_delta: U64::from(table.round_delta),
+            host_bps: table.host_bps,
+            shared_bankroll: table.shared_bankroll,
+            bankroll_amount: U128::from(table.bankroll_amount),
+            round_index: U64::from(table.round_index),
+            next_round_block_index: U64::from(table.next_round_block_index),
+            bets: table.bets.into_iter().map(|(account_id, bets)| (account_id, bets.iter().map(|bet| BetInfo {
+                bet_type: bet.bet_type,
+                number: bet.number,
+                chips: U128::from(bet.chips),
+            }).collect())).collect(),
+            bet_amount: U128::from(table.bet_amount),
+            last_number: table.last_number,
+            closed: table.closed,
+            tournament_id: table.tournament_id.map(U64::from),
+        })
+    }
+
+    pub fn get_private_table_count(&self) -> U64 {
+        U64::from(self.tables.len())
+    }
+
+    pub fn get_table_limits(&self) -> TableLimitsInfo {
+        let limits = &self.config.table_limits;
+        TableLimitsInfo {
+            min_chips: limits.min_chips.iter().map(|chips| U128::from(*chips)).collect(),
+            max_chips: limits.max_chips.iter().map(|chips| U128::from(*chips)).collect(),
+            chip_unit: U128::from(limits.chip_unit),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::*;
+
+pub const POCKETS: usize = 37;
+pub const MAX_WHEEL_WINDOW: u32 = 1000;     // bounds the recent spins loaded by every spin
+
+/*
+winning numbers of the wheel, to show recent results and watch the health of the random numbers
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone)]
+pub struct WheelStats {
+    pub counts: Vec<u64>,       // all time wins by pocket
+    pub recent: Vec<u8>,        // last wheel_window winning numbers, oldest first
+}
+
+/*
+what a set of spins looks like, colors are 0 for black, 1 for red and 2 for green as in the color bets
+*/
+#[derive(Debug, Clone, Default)]
+pub struct Distribution {
+    pub spins: u64,
+    pub counts: Vec<u64>,       // by pocket
+    pub colors: [u64; 3],
+    pub dozens: [u64; 3],
+    pub columns: [u64; 3],
+    pub odd_even: [u64; 2],     // even first as in the even/odd bets
+    pub low_high: [u64; 2],
+    pub hot_numbers: Vec<u8>,   // most frequent first
+    pub cold_numbers: Vec<u8>,  // least frequent first
+    pub chi_square_milli: u64,  // chi-square statistic versus a uniform wheel, times 1000
+}
+
+pub fn pocket_color(number: u8) -> u8 {
+    if number == 0 {
+        2
+    } else if check_win(number, &Bet { bet_type: 0, number: 1, chips: 0 }) {
+        1
+    } else {
+        0
+    }
+}
+
+/*
+the dozen, column, even/odd and low/high classes of a number follow the outside bets, 0 is in none of them
+*/
+pub fn distribution(counts: &Vec<u64>, top: usize) -> Distribution {
+    let mut stats = Distribution {
+        counts: counts.clone(),
+        ..Default::default()
+    };
+    for (number, count) in counts.iter().enumerate() {
+        let number = number as u8;
+        stats.spins += count;
+        stats.colors[pocket_color(number) as usize] += count;
+        if number > 0 {
+            stats.dozens[((number - 1) / 12) as usize] += count;
+            stats.columns[((number - 1) % 3) as usize] += count;
+            stats.odd_even[(number % 2) as usize] += count;
+            stats.low_high[if number <= 18 { 0 } else { 1 }] += count;
+        }
+    }
+    let mut numbers: Vec<u8> = (0..counts.len() as u8).collect();
+    numbers.sort_by(|a, b| counts[*b as usize].cmp(&counts[*a as usize]));     // stable, lower number first on a tie
+    stats.hot_numbers = numbers.iter().take(top).cloned().collect();
+    numbers.sort_by(|a, b| counts[*a as usize].cmp(&counts[*b as usize]));
+    stats.cold_numbers = numbers.iter().take(top).cloned().collect();
+    if stats.spins > 0 {
+        let pockets = counts.len() as u128;
+        let spins = stats.spins as u128;
+        let deviation = counts.iter().fold(0u128, |total, count| {       // sum of (observed - expected)^2 / expected, scaled by the pockets
+            let diff = (*count as u128 * pockets) as i128 - spins as i128;
+            total + (diff * diff) as u128
+        });
+        stats.chi_square_milli = (deviation * 1000 / (pockets * spins)) as u64;
+    }
+    stats
+}
+
+/*
+(color, length) of the current run and the longest run of the same color, 0 breaks every run
+*/
+pub fn color_streaks(numbers: &Vec<u8>) -> ((u8, u32), (u8, u32)) {
+    let mut current = (2u8, 0u32);
+    let mut longest = (2u8, 0u32);
+    for number in numbers.iter() {
+        let color = pocket_color(*number);
+        if color == current.0 && color != 2 {
+            current.1 += 1;
+        } else {
+            current = (color, if color == 2 { 0 } else { 1 });
+        }
+        if current.1 > longest.1 {
+            longest = current;
+        }
+    }
+    (current, longest)
+}
+
+impl WheelStats {
+    pub fn new() -> Self {
+        WheelStats {
+            counts: vec![0; POCKETS],
+            recent: Vec::new(),
+        }
+    }
+
+    /*
+    a smaller window takes effect at the next spin
+    */
+    pub fn record(&mut self, number: u8, window: u32) {
+        self.counts[number as usize] += 1;
+        self.recent.push(number);
+        let window = window as usize;
+        if self.recent.len() > window {
+            self.recent.drain(..self.recent.len() - window);
+        }
+    }
+
+    pub fn recent_counts(&self) -> Vec<u64> {
+        let mut counts = vec![0u64; POCKETS];
+        for number in self.recent.iter() {
+            counts[*number as usize] += 1;
+        }
+        counts
+    }
+}
+
+impl Contract {
+    /*
+    the main wheel without table_id, else the wheel of a private table
+    */
+    pub(crate) fn wheel_stats_of(&self, table_id: Option<u64>) -> WheelStats {
+        match table_id {
+            None => self.wheel_stats.clone(),
+            Some(table_id) => {
+                require(table_id < self.tables.len(), RoulefiError::TableNotFound);
+                self.table_wheel_stats.get(&table_id).unwrap_or_else(WheelStats::new)
+            },
+        }
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribution_of_spins() {
+        let mut wheel = WheelStats::new();
+        for number in [1, 1, 2, 0, 36, 19, 1].iter() {
+            wheel.record(*number, 200);
+        }
+        let stats = distribution(&wheel.counts, 3);
+        assert_eq!(stats.spins, 7);
+        assert_eq!(stats.colors, [2, 4, 1]);        // 2, 19 black; 1, 36 red
+        assert_eq!(stats.dozens, [4, 1, 1]);
+        assert_eq!(stats.columns, [4, 1, 1]);
+        assert_eq!(stats.odd_even, [2, 4]);
+        assert_eq!(stats.low_high, [4, 2]);
+        assert_eq!(stats.hot_numbers, vec![1, 0, 2]);
+        assert_eq!(stats.cold_numbers, vec![3, 4, 5]);
+
+        let uniform = distribution(&vec![2; POCKETS], 3);
+        assert_eq!(uniform.chi_square_milli, 0);
+        let mut skewed = vec![0; POCKETS];
+        skewed[17] = 37;
+        assert_eq!(distribution(&skewed, 3).chi_square_milli, 1332000);     // (37 - 1)^2 + 36 * (0 - 1)^2
+    }
+
+    #[test]
+    fn streaks_and_window() {
+        let (current, longest) = color_streaks(&vec![1, 3, 5, 2, 0, 4, 6]);
+        assert_eq!(current, (0, 2));
+        assert_eq!(longest, (1, 3));
+
+        let mut wheel = WheelStats::new();
+        for i in 0..210 {
+            wheel.record((i % POCKETS) as u8, 200);
+        }
+        assert_eq!(wheel.recent.len(), 200);
+        assert_eq!(wheel.recent[0], 10);
+        assert_eq!(wheel.recent_counts().iter().sum::<u64>(), 200);
+        assert_eq!(wheel.counts.iter().sum::<u64>(), 210);
+        wheel.record(0, 50);
+        assert_eq!(wheel.recent.len(), 50);
+        assert_eq!(wheel.recent[49], 0);
+    }
+}